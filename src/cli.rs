@@ -1,5 +1,9 @@
+use std::path::PathBuf;
+
 use clap::{Parser, Subcommand};
 
+use crate::commands::run::OutputFormat;
+
 #[derive(Parser, Debug)]
 #[command(
     name = "NeuroRISC",
@@ -14,6 +18,86 @@ pub struct Cli {
 
 #[derive(Subcommand, Debug)]
 pub enum Commands {
-    Run,
+    /// Simulate a graph saved from the editor, or the sensory circuit demo without one
+    Run {
+        graph: Option<PathBuf>,
+        #[arg(long, default_value_t = 500)]
+        steps: usize,
+        /// Timestep in ms
+        #[arg(long, default_value_t = 0.1)]
+        dt: f64,
+        /// Seed for Poisson stimuli, or for the demo circuit's random parameters
+        #[arg(long)]
+        seed: Option<u64>,
+        /// Record voltages every this many ticks
+        #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u64).range(1..))]
+        sample_every: u64,
+        /// Output path; every format replaces its extension or adds a suffix
+        #[arg(long, default_value = "network_activity")]
+        out: PathBuf,
+        /// Comma-separated output formats
+        #[arg(long, value_enum, value_delimiter = ',', default_value = "html")]
+        format: Vec<OutputFormat>,
+    },
     Gui,
+    /// Execute an RV32 ELF executable, an assembly file (.s) or a flat binary at 0x80000000
+    Exec {
+        program: PathBuf,
+        /// Run ALU ops on spiking circuits and check them against the reference ALU
+        #[arg(long)]
+        spiking: bool,
+        #[arg(long, default_value_t = 1_000_000)]
+        max_steps: u64,
+        /// Write a Spike-style commit log of every retired instruction to this file
+        #[arg(long)]
+        trace: Option<PathBuf>,
+        /// Attach the sensory circuit to the spike device, with ports `input` and `output`
+        #[arg(long)]
+        sensory: bool,
+        /// Hold the registers in spiking latches instead of plain words
+        #[arg(long)]
+        spiking_regs: bool,
+        /// Noise amplitude on the spiking latches, in threshold units
+        #[arg(long, default_value_t = 0.0, requires = "spiking_regs")]
+        reg_noise: f64,
+    },
+    /// Serve a program to GDB over the remote serial protocol
+    Debug {
+        program: PathBuf,
+        /// TCP port to listen on, on localhost
+        #[arg(long)]
+        gdb: u16,
+        /// Run ALU ops on spiking circuits and check them against the reference ALU
+        #[arg(long)]
+        spiking: bool,
+    },
+    /// Run riscv-tests ISA test ELFs and report pass/fail through `tohost`
+    Compliance {
+        dir: PathBuf,
+        /// Also run every test with ALU ops on spiking circuits
+        #[arg(long)]
+        spiking: bool,
+        /// Only run tests whose file name contains this string
+        #[arg(long)]
+        filter: Option<String>,
+        #[arg(long, default_value_t = 1_000_000)]
+        max_steps: u64,
+    },
+    /// Measure how reliably the spiking register file holds values as noise increases
+    Retention {
+        /// Ticks to hold the stored values before reading them back
+        #[arg(long, default_value_t = 1000)]
+        hold: u32,
+        /// Highest noise amplitude, in threshold units
+        #[arg(long, default_value_t = 2.0)]
+        max_noise: f64,
+        /// Number of evenly spaced noise levels from 0 to `max_noise`
+        #[arg(long, default_value_t = 11)]
+        levels: u32,
+        /// Neurons per bit
+        #[arg(long, default_value_t = 3)]
+        population: usize,
+        #[arg(long, default_value_t = 0)]
+        seed: u64,
+    },
 }
@@ -0,0 +1,215 @@
+use std::{fmt, fs, path::Path};
+
+use anyhow::{Context, bail};
+
+use neurorisc::core::{
+    cpu::{Cpu, Memory, Trap},
+    elf::{self, ElfImage},
+    hybrid::HybridAlu,
+};
+
+use crate::commands::exec::{RAM_BASE, RAM_SIZE};
+
+enum Outcome {
+    Pass,
+    /// riscv-tests report the number of the failing case.
+    Fail(u32),
+    /// A trap with no handler installed.
+    Trap(Trap),
+    Timeout,
+    Error(String),
+}
+
+impl Outcome {
+    fn passed(&self) -> bool {
+        matches!(self, Outcome::Pass)
+    }
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Outcome::Pass => write!(f, "pass"),
+            Outcome::Fail(case) => write!(f, "FAIL #{case}"),
+            Outcome::Trap(trap) => write!(f, "TRAP {}", trap.cause()),
+            Outcome::Timeout => write!(f, "TIMEOUT"),
+            Outcome::Error(_) => write!(f, "ERROR"),
+        }
+    }
+}
+
+struct TestRun {
+    outcome: Outcome,
+    instret: u64,
+    alu_ops: usize,
+    mismatches: usize,
+}
+
+impl TestRun {
+    fn error(error: &anyhow::Error) -> Self {
+        Self {
+            outcome: Outcome::Error(format!("{error:#}")),
+            instret: 0,
+            alu_ops: 0,
+            mismatches: 0,
+        }
+    }
+}
+
+/// Runs every ELF in `dir` whose file name contains `filter`. Each test
+/// reports through the `tohost` word: 1 is a pass and `case << 1 | 1` a
+/// failure.
+pub fn run(dir: &Path, spiking: bool, filter: Option<&str>, max_steps: u64) -> anyhow::Result<()> {
+    let mut paths = fs::read_dir(dir)
+        .with_context(|| format!("failed to read {}", dir.display()))?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()?;
+    paths.sort();
+
+    let mut tests = Vec::new();
+    for path in paths {
+        let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+            continue;
+        };
+        if !path.is_file() || filter.is_some_and(|filter| !name.contains(filter)) {
+            continue;
+        }
+
+        let bytes =
+            fs::read(&path).with_context(|| format!("failed to read {}", path.display()))?;
+        if elf::is_elf(&bytes) {
+            tests.push((name.to_string(), elf::parse(&bytes)));
+        }
+    }
+
+    if tests.is_empty() {
+        bail!("no test ELFs found in {}", dir.display());
+    }
+
+    let width = tests.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+    print!(
+        "{:<width$} {:>12} {:>10}",
+        "test", "conventional", "instret"
+    );
+    if spiking {
+        print!(" {:>12} {:>10} {:>10}", "spiking", "alu ops", "mismatches");
+    }
+    println!();
+
+    let mut passed = 0;
+    let mut spiking_passed = 0;
+    let mut errors = Vec::new();
+
+    for (name, image) in &tests {
+        let run = |spiking| match image {
+            Ok(image) => run_test(image, spiking, max_steps).unwrap_or_else(|e| TestRun::error(&e)),
+            Err(e) => TestRun::error(e),
+        };
+
+        let conventional = run(false);
+        passed += conventional.outcome.passed() as usize;
+        print!(
+            "{name:<width$} {:>12} {:>10}",
+            conventional.outcome.to_string(),
+            conventional.instret
+        );
+        if let Outcome::Error(message) = &conventional.outcome {
+            errors.push(format!("{name}: {message}"));
+        }
+
+        if spiking {
+            let hybrid = run(true);
+            spiking_passed += hybrid.outcome.passed() as usize;
+            print!(
+                " {:>12} {:>10} {:>10}",
+                hybrid.outcome.to_string(),
+                hybrid.alu_ops,
+                hybrid.mismatches
+            );
+        }
+        println!();
+    }
+
+    println!();
+    println!("conventional: {passed}/{} passed", tests.len());
+    if spiking {
+        println!("spiking:      {spiking_passed}/{} passed", tests.len());
+    }
+    for error in &errors {
+        println!("{error}");
+    }
+
+    let runs = if spiking {
+        2 * tests.len()
+    } else {
+        tests.len()
+    };
+    let failed = runs - passed - spiking_passed;
+    if failed > 0 {
+        bail!("{failed} test runs failed");
+    }
+
+    Ok(())
+}
+
+fn run_test(image: &ElfImage, spiking: bool, max_steps: u64) -> anyhow::Result<TestRun> {
+    let tohost = image.tohost().context("no `tohost` symbol")?;
+
+    let mut memory = Memory::new(RAM_BASE, RAM_SIZE);
+    image.load(&mut memory)?;
+
+    let mut cpu = Cpu::new(memory);
+    cpu.pc = image.entry;
+    if spiking {
+        cpu.hybrid = Some(HybridAlu::new()?);
+    }
+
+    let mut outcome = Outcome::Timeout;
+    for _ in 0..max_steps {
+        // Tests install their own handler, so a trap only ends the run when
+        // there is nowhere to go.
+        if let Err(trap) = cpu.step()
+            && cpu.csrs.mtvec == 0
+        {
+            outcome = Outcome::Trap(trap);
+            break;
+        }
+
+        // Some suites power off through the test finisher instead.
+        match cpu.bus.finisher.exit {
+            Some(0) => {
+                outcome = Outcome::Pass;
+                break;
+            }
+            Some(status) => {
+                outcome = Outcome::Fail(status);
+                break;
+            }
+            None => {}
+        }
+
+        match cpu.memory.load(tohost, 4)? {
+            0 => {}
+            1 => {
+                outcome = Outcome::Pass;
+                break;
+            }
+            value => {
+                outcome = Outcome::Fail(value >> 1);
+                break;
+            }
+        }
+    }
+
+    let (alu_ops, mismatches) = cpu
+        .hybrid
+        .as_ref()
+        .map_or((0, 0), |hybrid| (hybrid.ops(), hybrid.mismatch_count()));
+
+    Ok(TestRun {
+        outcome,
+        instret: cpu.csrs.instret,
+        alu_ops,
+        mismatches,
+    })
+}
@@ -0,0 +1,408 @@
+use std::{
+    collections::HashSet,
+    io::{ErrorKind, Read, Write},
+    net::{TcpListener, TcpStream},
+    path::Path,
+};
+
+use anyhow::Context;
+
+use neurorisc::core::{
+    cpu::{Cpu, Trap},
+    csr,
+    instructions::ABI_NAMES,
+};
+
+use crate::commands::exec;
+
+const PC_REGNUM: usize = 32;
+/// GDB numbers CSRs after the x registers, pc and the 32 f registers.
+const CSR_REGNUM: usize = 65;
+/// Steps between checks for a Ctrl-C from GDB while continuing.
+const INTERRUPT_POLL: u64 = 4096;
+
+enum Stop {
+    Step,
+    Breakpoint,
+    Interrupt,
+    /// A trap with no handler installed.
+    Trap(Trap),
+    /// The guest powered off through the test finisher.
+    Exit(u32),
+}
+
+impl Stop {
+    /// POSIX signal number reported to GDB.
+    fn signal(&self) -> u8 {
+        match self {
+            Stop::Interrupt => 2,
+            Stop::Trap(Trap::IllegalInstruction(_)) => 4,
+            Stop::Trap(
+                Trap::InstructionAddressMisaligned(_)
+                | Trap::LoadAddressMisaligned(_)
+                | Trap::StoreAddressMisaligned(_),
+            ) => 7,
+            Stop::Trap(
+                Trap::InstructionAccessFault(_)
+                | Trap::LoadAccessFault(_)
+                | Trap::StoreAccessFault(_),
+            ) => 11,
+            _ => 5,
+        }
+    }
+}
+
+/// Serves one GDB connection on `127.0.0.1:port` for the loaded program.
+pub fn run(program: &Path, port: u16, spiking: bool) -> anyhow::Result<()> {
+    let cpu = exec::load(program, spiking)?;
+
+    let listener = TcpListener::bind(("127.0.0.1", port))
+        .with_context(|| format!("failed to listen on port {port}"))?;
+    println!("Waiting for GDB on 127.0.0.1:{port}");
+    let (stream, peer) = listener.accept()?;
+    stream.set_nodelay(true)?;
+    println!("GDB connected from {peer}");
+
+    let mut stub = Stub {
+        cpu,
+        stream,
+        breakpoints: HashSet::new(),
+    };
+    stub.serve()?;
+
+    println!("GDB disconnected");
+    Ok(())
+}
+
+struct Stub {
+    cpu: Cpu,
+    stream: TcpStream,
+    breakpoints: HashSet<u32>,
+}
+
+impl Stub {
+    fn serve(&mut self) -> anyhow::Result<()> {
+        while let Some(packet) = self.read_packet()? {
+            match self.handle(&packet)? {
+                Some(reply) => self.write_packet(&reply)?,
+                None => break,
+            }
+        }
+        Ok(())
+    }
+
+    fn read_byte(&mut self) -> anyhow::Result<Option<u8>> {
+        let mut byte = [0];
+        Ok(match self.stream.read(&mut byte)? {
+            0 => None,
+            _ => Some(byte[0]),
+        })
+    }
+
+    /// Reads `$payload#checksum`, acking it. Returns `None` once GDB hangs up.
+    fn read_packet(&mut self) -> anyhow::Result<Option<String>> {
+        loop {
+            // Acks and stray bytes between packets are ignored.
+            loop {
+                match self.read_byte()? {
+                    None => return Ok(None),
+                    Some(b'$') => break,
+                    Some(_) => {}
+                }
+            }
+
+            let mut payload = Vec::new();
+            loop {
+                match self.read_byte()? {
+                    None => return Ok(None),
+                    Some(b'#') => break,
+                    Some(byte) => payload.push(byte),
+                }
+            }
+
+            let mut checksum = [0; 2];
+            self.stream.read_exact(&mut checksum)?;
+            let valid = std::str::from_utf8(&checksum)
+                .ok()
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                == Some(checksum_of(&payload));
+
+            if valid {
+                self.stream.write_all(b"+")?;
+                return Ok(Some(String::from_utf8_lossy(&payload).into_owned()));
+            }
+            self.stream.write_all(b"-")?;
+        }
+    }
+
+    fn write_packet(&mut self, payload: &str) -> anyhow::Result<()> {
+        let checksum = checksum_of(payload.as_bytes());
+        write!(self.stream, "${payload}#{checksum:02x}")?;
+        Ok(())
+    }
+
+    /// Returns the reply, or `None` when GDB kills the session.
+    fn handle(&mut self, packet: &str) -> anyhow::Result<Option<String>> {
+        let command = packet.get(..1).unwrap_or("");
+        let args = packet.get(1..).unwrap_or("");
+
+        let reply = match command {
+            "?" => "S05".to_string(),
+            "g" => (0..=PC_REGNUM)
+                .map(|n| hex_u32(self.read_register(n).unwrap_or(0)))
+                .collect(),
+            "G" => {
+                for (n, chunk) in args.as_bytes().chunks(8).take(PC_REGNUM + 1).enumerate() {
+                    let value = std::str::from_utf8(chunk).ok().and_then(parse_hex_u32);
+                    if let Some(value) = value {
+                        self.write_register(n, value);
+                    }
+                }
+                "OK".to_string()
+            }
+            "p" => usize::from_str_radix(args, 16)
+                .ok()
+                .and_then(|n| self.read_register(n))
+                .map_or_else(|| "E01".to_string(), hex_u32),
+            "P" => {
+                let written = args.split_once('=').and_then(|(n, value)| {
+                    let n = usize::from_str_radix(n, 16).ok()?;
+                    self.write_register(n, parse_hex_u32(value)?).then_some(())
+                });
+                reply_ok(written)
+            }
+            "m" => parse_range(args)
+                .and_then(|(addr, len)| self.cpu.memory.read_bytes(addr, len).ok())
+                .map_or_else(
+                    || "E01".to_string(),
+                    |bytes| bytes.iter().map(|b| format!("{b:02x}")).collect(),
+                ),
+            "M" => {
+                let written = args.split_once(':').and_then(|(range, data)| {
+                    let (addr, len) = parse_range(range)?;
+                    let bytes = parse_hex_bytes(data)?;
+                    (bytes.len() == len).then_some(())?;
+                    self.cpu.memory.write_bytes(addr, &bytes).ok()
+                });
+                reply_ok(written)
+            }
+            "s" | "c" => {
+                if let Ok(addr) = u32::from_str_radix(args, 16) {
+                    self.cpu.pc = addr;
+                }
+                match self.resume(command == "s")? {
+                    // GDB only sees the low byte of an exit status.
+                    Stop::Exit(status) => format!("W{:02x}", status as u8),
+                    stop => format!("S{:02x}", stop.signal()),
+                }
+            }
+            "Z" | "z" => {
+                // Types 0 and 1 are software and hardware breakpoints; both
+                // stop before the instruction at the address executes.
+                let mut parts = args.split(',');
+                let kind = parts.next();
+                let addr = parts
+                    .next()
+                    .and_then(|addr| u32::from_str_radix(addr, 16).ok());
+
+                match (kind, addr) {
+                    (Some("0" | "1"), Some(addr)) => {
+                        if command == "Z" {
+                            self.breakpoints.insert(addr);
+                        } else {
+                            self.breakpoints.remove(&addr);
+                        }
+                        "OK".to_string()
+                    }
+                    _ => String::new(),
+                }
+            }
+            "H" => "OK".to_string(),
+            "k" => return Ok(None),
+            "D" => {
+                self.write_packet("OK")?;
+                return Ok(None);
+            }
+            "q" => self.query(args),
+            _ => String::new(),
+        };
+
+        Ok(Some(reply))
+    }
+
+    fn query(&self, query: &str) -> String {
+        if query.starts_with("Supported") {
+            return "PacketSize=4000;qXfer:features:read+".to_string();
+        }
+        if let Some(request) = query.strip_prefix("Xfer:features:read:target.xml:") {
+            let Some((offset, len)) = request.split_once(',').and_then(|(offset, len)| {
+                Some((
+                    usize::from_str_radix(offset, 16).ok()?,
+                    usize::from_str_radix(len, 16).ok()?,
+                ))
+            }) else {
+                return "E01".to_string();
+            };
+
+            let xml = target_xml();
+            let chunk = xml.get(offset..).unwrap_or("");
+            return if chunk.len() <= len {
+                format!("l{chunk}")
+            } else {
+                format!("m{}", &chunk[..len])
+            };
+        }
+
+        match query {
+            "Attached" => "1".to_string(),
+            "C" => "QC1".to_string(),
+            "fThreadInfo" => "m1".to_string(),
+            "sThreadInfo" => "l".to_string(),
+            _ => String::new(),
+        }
+    }
+
+    fn read_register(&self, n: usize) -> Option<u32> {
+        match n {
+            0..PC_REGNUM => Some(self.cpu.regs[n]),
+            PC_REGNUM => Some(self.cpu.pc),
+            _ => self
+                .cpu
+                .csrs
+                .read(u16::try_from(n.checked_sub(CSR_REGNUM)?).ok()?),
+        }
+    }
+
+    fn write_register(&mut self, n: usize, value: u32) -> bool {
+        match n {
+            0 => true,
+            1..PC_REGNUM => {
+                self.cpu.regs[n] = value;
+                true
+            }
+            PC_REGNUM => {
+                self.cpu.pc = value;
+                true
+            }
+            _ => n
+                .checked_sub(CSR_REGNUM)
+                .and_then(|csr| u16::try_from(csr).ok())
+                .and_then(|csr| self.cpu.csrs.write(csr, value))
+                .is_some(),
+        }
+    }
+
+    /// Runs one instruction, or until a breakpoint, an unhandled trap or a
+    /// Ctrl-C from GDB.
+    fn resume(&mut self, single: bool) -> anyhow::Result<Stop> {
+        let mut steps = 0u64;
+
+        loop {
+            if let Err(trap) = self.cpu.step()
+                && self.cpu.csrs.mtvec == 0
+            {
+                // Report the trapping instruction instead of the empty vector.
+                self.cpu.pc = self.cpu.csrs.mepc;
+                return Ok(Stop::Trap(trap));
+            }
+            if let Some(status) = self.cpu.bus.finisher.exit {
+                return Ok(Stop::Exit(status));
+            }
+            if single {
+                return Ok(Stop::Step);
+            }
+            if self.breakpoints.contains(&self.cpu.pc) {
+                return Ok(Stop::Breakpoint);
+            }
+
+            steps += 1;
+            if steps.is_multiple_of(INTERRUPT_POLL) && self.interrupted()? {
+                return Ok(Stop::Interrupt);
+            }
+        }
+    }
+
+    /// Checks for a pending Ctrl-C (0x03) without blocking. A closed
+    /// connection also stops execution.
+    fn interrupted(&mut self) -> anyhow::Result<bool> {
+        self.stream.set_nonblocking(true)?;
+        let mut byte = [0];
+        let result = self.stream.read(&mut byte);
+        self.stream.set_nonblocking(false)?;
+
+        match result {
+            Ok(0) => Ok(true),
+            Ok(_) => Ok(byte[0] == 0x03),
+            Err(e) if e.kind() == ErrorKind::WouldBlock => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+fn target_xml() -> String {
+    let mut xml = String::from(
+        r#"<?xml version="1.0"?><!DOCTYPE target SYSTEM "gdb-target.dtd"><target version="1.0"><architecture>riscv:rv32</architecture><feature name="org.gnu.gdb.riscv.cpu">"#,
+    );
+    for (n, name) in ABI_NAMES.iter().enumerate() {
+        let kind = match n {
+            1 => "code_ptr",
+            2 => "data_ptr",
+            _ => "int",
+        };
+        xml += &format!(r#"<reg name="{name}" bitsize="32" type="{kind}" regnum="{n}"/>"#);
+    }
+    xml += &format!(r#"<reg name="pc" bitsize="32" type="code_ptr" regnum="{PC_REGNUM}"/>"#);
+    xml += r#"</feature><feature name="org.gnu.gdb.riscv.csr">"#;
+    for (addr, name) in csr::NAMES {
+        xml += &format!(
+            r#"<reg name="{name}" bitsize="32" type="int" regnum="{}"/>"#,
+            CSR_REGNUM + addr as usize
+        );
+    }
+    xml += "</feature></target>";
+    xml
+}
+
+fn checksum_of(payload: &[u8]) -> u8 {
+    payload.iter().fold(0u8, |sum, &b| sum.wrapping_add(b))
+}
+
+fn reply_ok(result: Option<()>) -> String {
+    match result {
+        Some(()) => "OK".to_string(),
+        None => "E01".to_string(),
+    }
+}
+
+/// Registers travel as target-endian (little-endian) hex.
+fn hex_u32(value: u32) -> String {
+    value
+        .to_le_bytes()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+fn parse_hex_bytes(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+fn parse_hex_u32(hex: &str) -> Option<u32> {
+    let bytes: [u8; 4] = parse_hex_bytes(hex)?.try_into().ok()?;
+    Some(u32::from_le_bytes(bytes))
+}
+
+/// Parses `addr,length`.
+fn parse_range(text: &str) -> Option<(u32, usize)> {
+    let (addr, len) = text.split_once(',')?;
+    Some((
+        u32::from_str_radix(addr, 16).ok()?,
+        usize::from_str_radix(len, 16).ok()?,
+    ))
+}
@@ -0,0 +1,221 @@
+use std::{
+    fs::{self, File},
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+use anyhow::{Context, bail};
+
+use neurorisc::{
+    core::{
+        asm,
+        bus::{SpikeDevice, SpikePort},
+        cpu::{Cpu, Memory},
+        elf,
+        hybrid::HybridAlu,
+        spiking_regfile::{RegisterFileParams, SpikingRegisterFile},
+        templates::{CircuitParams, build_sensory_circuit},
+    },
+    neuro::network::Network,
+};
+
+pub const RAM_BASE: u32 = 0x8000_0000;
+pub const RAM_SIZE: usize = 16 << 20;
+
+/// Loads an ELF executable, an assembly file or a flat binary into a fresh
+/// CPU, with pc at its entry point.
+pub fn load(program: &Path, spiking: bool) -> anyhow::Result<Cpu> {
+    let image =
+        fs::read(program).with_context(|| format!("failed to read {}", program.display()))?;
+
+    let mut memory = Memory::new(RAM_BASE, RAM_SIZE);
+    let mut entry = RAM_BASE;
+
+    if elf::is_elf(&image) {
+        let elf =
+            elf::parse(&image).with_context(|| format!("failed to parse {}", program.display()))?;
+        elf.load(&mut memory)?;
+        entry = elf.entry;
+    } else if program
+        .extension()
+        .is_some_and(|ext| ext == "s" || ext == "S")
+    {
+        let source = String::from_utf8(image).context("assembly source is not UTF-8")?;
+        let assembled = asm::assemble(&source, RAM_BASE)
+            .with_context(|| format!("failed to assemble {}", program.display()))?;
+        memory
+            .write_bytes(RAM_BASE, &assembled.to_bytes())
+            .context("program does not fit in memory")?;
+    } else {
+        memory
+            .write_bytes(RAM_BASE, &image)
+            .context("program does not fit in memory")?;
+    }
+
+    let mut cpu = Cpu::new(memory);
+    cpu.pc = entry;
+    if spiking {
+        cpu.hybrid = Some(HybridAlu::new()?);
+    }
+
+    Ok(cpu)
+}
+
+/// The sensory circuit from `run`, with its input and output neurons exposed
+/// to the guest as ports 0 and 1.
+fn sensory_device() -> anyhow::Result<SpikeDevice> {
+    let mut network = Network::new();
+    let params = CircuitParams {
+        strong_weight: 4.0,
+        inhibitory_weight: -10.0,
+        noise_amt: 10.0,
+    };
+    let (input, output) = build_sensory_circuit(&mut network, &params, &mut rand::rng())?;
+
+    let port = |name: &str, neuron| SpikePort {
+        name: name.to_string(),
+        neuron,
+        weight: 3.0,
+        count: 0,
+    };
+    let ports = vec![port("input", input), port("output", output)];
+
+    Ok(SpikeDevice::new(network, 0.1, ports))
+}
+
+pub fn run(
+    program: &Path,
+    spiking: bool,
+    max_steps: u64,
+    trace: Option<&Path>,
+    sensory: bool,
+    spiking_regs: Option<RegisterFileParams>,
+) -> anyhow::Result<()> {
+    let mut cpu = load(program, spiking)?;
+    if sensory {
+        cpu.bus.spikes = Some(sensory_device()?);
+    }
+    if let Some(params) = &spiking_regs {
+        cpu.spiking_regs = Some(SpikingRegisterFile::new(params)?);
+    }
+
+    let mut trace = trace
+        .map(|path| {
+            File::create(path)
+                .map(BufWriter::new)
+                .with_context(|| format!("failed to create {}", path.display()))
+        })
+        .transpose()?;
+    cpu.trace = trace.is_some();
+
+    let mut steps = 0;
+    let trap = loop {
+        if steps >= max_steps {
+            break None;
+        }
+        let result = cpu.step();
+        if let (Some(out), Some(commit)) = (&mut trace, &cpu.commit) {
+            writeln!(out, "{commit}")?;
+        }
+        // Programs that never install a handler stop at their first trap,
+        // usually a final `ecall`.
+        if let Err(trap) = result
+            && cpu.csrs.mtvec == 0
+        {
+            break Some(trap);
+        }
+        steps += 1;
+        if cpu.bus.finisher.exit.is_some() {
+            break None;
+        }
+    };
+
+    if let Some(out) = &mut trace {
+        out.flush()?;
+    }
+
+    match (trap, cpu.bus.finisher.exit) {
+        (Some(trap), _) => println!("Stopped after {steps} instructions: {trap}"),
+        (None, Some(status)) => {
+            println!("Powered off after {steps} instructions with status {status}")
+        }
+        (None, None) => println!("Step limit of {max_steps} instructions reached"),
+    }
+
+    for (i, regs) in cpu.regs.chunks(4).enumerate() {
+        let line = regs
+            .iter()
+            .enumerate()
+            .map(|(j, value)| format!("x{:<2} {value:#010x}", i * 4 + j))
+            .collect::<Vec<_>>()
+            .join("  ");
+        println!("{line}");
+    }
+    // An unhandled trap has already redirected pc to the empty `mtvec`.
+    let pc = if trap.is_some() {
+        cpu.csrs.mepc
+    } else {
+        cpu.pc
+    };
+    println!("pc  {pc:#010x}");
+    println!(
+        "cycle {}  instret {}  ntick {}",
+        cpu.csrs.cycle, cpu.csrs.instret, cpu.csrs.ntick
+    );
+
+    if let Some(file) = &cpu.spiking_regs {
+        println!(
+            "spiking regs: {} reads  {} writes  {} ticks  {} mismatched reads",
+            file.reads, file.writes, file.ticks, cpu.reg_mismatches
+        );
+    }
+
+    if let Some(device) = &cpu.bus.spikes {
+        println!();
+        println!("network t {}", device.network.t);
+        for (i, port) in device.ports.iter().enumerate() {
+            println!(
+                "port {i} {:<8} neuron {:<3} {} spikes",
+                port.name, port.neuron, port.count
+            );
+        }
+    }
+
+    if let Some(hybrid) = &cpu.hybrid {
+        println!();
+        println!(
+            "{:<6} {:>8} {:>10} {:>10} {:>8} {:>10}",
+            "op", "count", "avg ticks", "spikes", "neurons", "mismatches"
+        );
+        for (op, summary) in hybrid.summary() {
+            println!(
+                "{:<6} {:>8} {:>10.1} {:>10} {:>8} {:>10}",
+                op,
+                summary.count,
+                summary.ticks as f64 / summary.count as f64,
+                summary.spikes,
+                summary.neurons,
+                summary.mismatches
+            );
+        }
+
+        for sample in hybrid.mismatches() {
+            println!(
+                "MISMATCH pc={:#010x} `{}` a={:#010x} b={:#010x} spiking={:#010x} reference={:#010x}",
+                sample.pc, sample.instruction, sample.a, sample.b, sample.value, sample.expected
+            );
+        }
+        let unshown = hybrid.mismatch_count() - hybrid.mismatches().len();
+        if unshown > 0 {
+            println!("... and {unshown} more mismatches");
+        }
+    }
+
+    if let Some(status) = cpu.bus.finisher.exit
+        && status != 0
+    {
+        bail!("guest exited with status {status}");
+    }
+
+    Ok(())
+}
@@ -1,2 +1,6 @@
+pub mod compliance;
+pub mod debug;
+pub mod exec;
 pub mod gui;
+pub mod retention;
 pub mod run;
@@ -0,0 +1,43 @@
+use neurorisc::core::spiking_regfile::{RegisterFileParams, measure_retention};
+
+/// Stores random words in every register of a fresh spiking register file at
+/// each noise level and reports how many bits survive `hold` ticks.
+pub fn run(
+    hold: u32,
+    max_noise: f64,
+    levels: u32,
+    population: usize,
+    seed: u64,
+) -> anyhow::Result<()> {
+    anyhow::ensure!(levels >= 1, "need at least one noise level");
+
+    println!(
+        "{:>8} {:>8} {:>8} {:>8} {:>12}",
+        "noise", "bits", "lost", "gained", "reliability"
+    );
+
+    for level in 0..levels {
+        let noise = if levels == 1 {
+            max_noise
+        } else {
+            max_noise * level as f64 / (levels - 1) as f64
+        };
+        let params = RegisterFileParams {
+            population,
+            noise,
+            seed,
+        };
+
+        let retention = measure_retention(&params, hold)?;
+        println!(
+            "{:>8.3} {:>8} {:>8} {:>8} {:>12.4}",
+            retention.noise,
+            retention.bits,
+            retention.lost,
+            retention.gained,
+            retention.reliability()
+        );
+    }
+
+    Ok(())
+}
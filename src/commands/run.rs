@@ -1,17 +1,38 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+
 use plotly::{
     Layout, Plot, Scatter,
     common::Mode,
     layout::{Axis, Shape, ShapeLine, ShapeType},
 };
+use rand::{SeedableRng, rngs::StdRng};
 
-use crate::{
+use neurorisc::{
     core::templates::{CircuitParams, build_sensory_circuit},
-    neuro::network::Network,
+    neuro::{
+        export::Recording,
+        monitor::{StateMonitor, Variable},
+        network::Network,
+        recorder::SpikeRecorder,
+        stimuli::{StimulusMode, StimulusRunner},
+    },
 };
 
-pub fn run() -> anyhow::Result<()> {
+use crate::gui::{builder::GraphNode, snapshot::GraphSnapshot};
+
+/// A network ready to tick, with a display name for every neuron.
+struct Simulation {
+    network: Network,
+    stimuli: StimulusRunner,
+    names: Vec<String>,
+}
+
+/// The sensory circuit with one spike into its input. `seed` fixes the
+/// circuit's random thresholds and weights.
+fn sensory_demo(dt: f64, seed: Option<u64>) -> anyhow::Result<Simulation> {
     let mut network = Network::new();
-    let dt = 0.1;
 
     let params = CircuitParams {
         strong_weight: 4.0,
@@ -19,50 +40,118 @@ pub fn run() -> anyhow::Result<()> {
         noise_amt: 10.0,
     };
 
-    let (input, _) = build_sensory_circuit(&mut network, &params)?;
+    let mut rng = match seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_rng(&mut rand::rng()),
+    };
+    let (input, _) = build_sensory_circuit(&mut network, &params, &mut rng)?;
 
     network.resize_events();
+    network.schedule_spike(input, 3.0, 0);
 
-    let mut history: Vec<Vec<f64>> = vec![vec![]; network.neurons.len()];
-    let mut times: Vec<f64> = Vec::new();
+    let names = (0..network.neurons.len())
+        .map(|id| format!("Neuron {id}"))
+        .collect();
 
-    network.schedule_spike(input, 3.0, 0);
+    Ok(Simulation {
+        network,
+        stimuli: StimulusRunner::new(dt),
+        names,
+    })
+}
 
-    let steps = 500;
-    for _ in 0..steps {
-        network.tick(dt);
+/// A graph saved by the editor, with every enabled stimulus node fired at
+/// t=0. `seed` replaces the seeds of Poisson stimuli, offset per stimulus so
+/// they stay independent.
+fn saved_graph(path: &Path, dt: f64, seed: Option<u64>) -> anyhow::Result<Simulation> {
+    let snapshot = GraphSnapshot::load(path)?;
+    let compiled = snapshot.compile()?;
+    anyhow::ensure!(
+        !compiled.network.neurons.is_empty(),
+        "{} has no neurons",
+        path.display()
+    );
 
-        times.push(network.t as f64);
+    let mut names: Vec<String> = (0..compiled.network.neurons.len())
+        .map(|id| format!("Neuron {id}"))
+        .collect();
+    for (&node, &id) in &compiled.node_to_neuron {
+        if let Some(GraphNode::Neuron(spec)) = snapshot.snarl.get_node(node)
+            && !spec.label.is_empty()
+        {
+            names[id] = spec.label.clone();
+        }
+    }
 
-        for (id, neuron) in network.neurons.iter().enumerate() {
-            let display_v = if neuron.state.refractory_left == neuron.config.refractory_period {
-                40.0
-            } else {
-                neuron.state.v
-            };
+    let mut stimuli = StimulusRunner::new(dt);
+    for (i, &(node, neuron)) in compiled.inputs.iter().enumerate() {
+        let Some(GraphNode::Stimulus(spec)) = snapshot.snarl.get_node(node) else {
+            continue;
+        };
+        if !spec.enabled {
+            continue;
+        }
 
-            history[id].push(display_v);
+        let mut spec = spec.clone();
+        if let (Some(seed), StimulusMode::Poisson { seed: own, .. }) = (seed, &mut spec.mode) {
+            *own = seed.wrapping_add(i as u64);
         }
+        stimuli.fire(i as u64, neuron, &spec, &compiled.network);
     }
 
+    Ok(Simulation {
+        network: compiled.network,
+        stimuli,
+        names,
+    })
+}
+
+/// What `run` writes, each next to `--out` with its own extension.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum OutputFormat {
+    /// Interactive plotly voltage plot
+    Html,
+    /// `_voltages.csv` and `_spikes.csv`
+    Csv,
+    /// One JSON document with metadata, voltages and spikes
+    Json,
+    /// One `.npy` file per array
+    Npy,
+    /// All arrays in one `.npz` archive
+    Npz,
+}
+
+fn write_html(recording: &Recording, path: &Path) -> anyhow::Result<()> {
     let mut plot = Plot::new();
 
-    for (id, voltages) in history.into_iter().enumerate() {
+    let times: Vec<f64> = recording.ticks.iter().map(|&tick| tick as f64).collect();
+    let mut traces = recording.voltages.clone();
+    for spike in &recording.spikes {
+        // Mark the spike on the last sample at or before it.
+        let sample = recording.ticks.partition_point(|&tick| tick <= spike.tick);
+        if let Some(sample) = sample.checked_sub(1) {
+            traces[spike.neuron][sample] = 40.0;
+        }
+    }
+    let first = recording.ticks.first().copied().unwrap_or(0);
+    let last = recording.ticks.last().copied().unwrap_or(0);
+
+    for (voltages, name) in traces.into_iter().zip(&recording.labels) {
         let trace = Scatter::new(times.clone(), voltages)
             .mode(Mode::Lines)
-            .name(format!("Neuron {}", id));
+            .name(name);
         plot.add_trace(trace);
     }
 
     let layout = Layout::new()
         .title("SNN Voltage Traces")
         .x_axis(Axis::new().title("Time (ticks)"))
-        .y_axis(Axis::new().title("Voltage (mV)").range(vec![-70.0, -40.0]))
+        .y_axis(Axis::new().title("Voltage (mV)"))
         .shapes(vec![
             Shape::new()
                 .shape_type(ShapeType::Line)
-                .x0(0)
-                .x1(steps)
+                .x0(first)
+                .x1(last)
                 .y0(-50.0)
                 .y1(-50.0)
                 .line(
@@ -75,8 +164,120 @@ pub fn run() -> anyhow::Result<()> {
 
     plot.set_layout(layout);
 
-    plot.write_html("network_activity.html");
-    println!("Interactive plot saved to 'network_activity.html'");
+    std::fs::write(path, plot.to_html())
+        .with_context(|| format!("failed to write {}", path.display()))
+}
+
+/// Every file `run` writes for `format` under the `out` stem.
+fn output_paths(out: &Path, format: OutputFormat) -> Vec<PathBuf> {
+    match format {
+        OutputFormat::Html => vec![out.with_extension("html")],
+        OutputFormat::Csv => Recording::csv_paths(out).into(),
+        OutputFormat::Json => vec![out.with_extension("json")],
+        OutputFormat::Npy => Recording::npy_paths(out),
+        OutputFormat::Npz => vec![out.with_extension("npz")],
+    }
+}
+
+/// Simulates `graph`, or the sensory circuit demo without one, and writes
+/// the recorded voltages and spikes in every format in `formats`.
+pub fn run(
+    graph: Option<&Path>,
+    steps: usize,
+    dt: f64,
+    seed: Option<u64>,
+    sample_every: u64,
+    out: &Path,
+    formats: &[OutputFormat],
+) -> anyhow::Result<()> {
+    anyhow::ensure!(dt.is_finite() && dt > 0.0, "dt must be positive");
+
+    let outputs: Vec<(OutputFormat, Vec<PathBuf>)> = formats
+        .iter()
+        .map(|&format| (format, output_paths(out, format)))
+        .collect();
+    if let Some(graph) = graph {
+        let graph = std::fs::canonicalize(graph)
+            .with_context(|| format!("failed to resolve {}", graph.display()))?;
+        for path in outputs.iter().flat_map(|(_, paths)| paths) {
+            anyhow::ensure!(
+                std::fs::canonicalize(path).ok().as_ref() != Some(&graph),
+                "output '{}' would overwrite the input graph",
+                path.display()
+            );
+        }
+    }
+
+    let Simulation {
+        mut network,
+        mut stimuli,
+        names,
+    } = match graph {
+        Some(path) => saved_graph(path, dt, seed)?,
+        None => sensory_demo(dt, seed)?,
+    };
+
+    let mut recording = Recording {
+        dt,
+        kinds: network.neurons.iter().map(|neuron| neuron.kind).collect(),
+        labels: names,
+        ..Recording::default()
+    };
+
+    let mut monitor = StateMonitor::new((0..network.neurons.len()).collect(), vec![Variable::V]);
+    monitor.set_interval(sample_every);
+    network.monitor = Some(monitor);
+    network.recorder = Some(SpikeRecorder::new());
+
+    for _ in 0..steps {
+        stimuli.apply(&mut network);
+        network.tick(dt);
+    }
+
+    let recorder = network.recorder.take().unwrap_or_default();
+    recording.spikes = recorder.spikes().collect();
+    if let Some(monitor) = network.monitor.take() {
+        recording.ticks = monitor.ticks().iter().copied().collect();
+        recording.voltages = monitor
+            .neurons()
+            .iter()
+            .map(|&id| {
+                monitor
+                    .trace(id, Variable::V)
+                    .map(|trace| trace.iter().copied().collect())
+                    .unwrap_or_default()
+            })
+            .collect();
+    }
+
+    let mut written = Vec::new();
+    for (format, paths) in outputs {
+        match format {
+            OutputFormat::Html => write_html(&recording, &paths[0])?,
+            OutputFormat::Csv => {
+                recording.write_csv(out)?;
+            }
+            OutputFormat::Json => recording.write_json(&paths[0])?,
+            OutputFormat::Npy => {
+                recording.write_npy(out)?;
+            }
+            OutputFormat::Npz => recording.write_npz(&paths[0])?,
+        }
+        written.extend(paths);
+    }
+
+    println!(
+        "Simulated {steps} steps of {dt} ms on {} neurons",
+        recording.labels.len()
+    );
+    let spikes = recorder.counts(recording.labels.len(), 0..network.t as u64);
+    let width = recording.labels.iter().map(String::len).max().unwrap_or(0);
+    for (name, count) in recording.labels.iter().zip(&spikes) {
+        println!("{name:<width$} {count:>6} spikes");
+    }
+    for path in written {
+        println!("Wrote '{}'", path.display());
+    }
 
     Ok(())
 }
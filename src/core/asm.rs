@@ -0,0 +1,713 @@
+use std::{collections::HashMap, fmt};
+
+use crate::core::{
+    csr,
+    decoder::{
+        OP, OP_AUIPC, OP_BRANCH, OP_IMM, OP_JAL, OP_JALR, OP_LOAD, OP_LUI, OP_MISC_MEM, OP_STORE,
+        OP_SYSTEM, encode,
+    },
+    instructions::{ABI_NAMES, DecodedInstruction},
+};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AsmError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for AsmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for AsmError {}
+
+#[derive(Clone, Debug)]
+pub struct Program {
+    pub base: u32,
+    pub words: Vec<u32>,
+    pub labels: HashMap<String, u32>,
+}
+
+impl Program {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.words.iter().flat_map(|w| w.to_le_bytes()).collect()
+    }
+}
+
+pub fn parse_register(name: &str) -> Option<u8> {
+    if name == "fp" {
+        return Some(8);
+    }
+    if let Some(index) = ABI_NAMES.iter().position(|&abi| abi == name) {
+        return Some(index as u8);
+    }
+
+    let index: u8 = name.strip_prefix('x')?.parse().ok()?;
+    (index < 32).then_some(index)
+}
+
+struct Statement<'a> {
+    line: usize,
+    mnemonic: &'a str,
+    operands: Vec<&'a str>,
+}
+
+/// Assembles RV32I source into machine words placed at `base`.
+///
+/// Supports labels (`name:`), `#` comments, ABI register names, `.word` and
+/// the pseudo-instructions `nop`, `li`, `la`, `mv`, `j`, `jr`, `ret`, `beqz`,
+/// `bnez`, `csrr`, `csrw`, `csrs`, `csrc` and the `i` forms of the last three.
+/// CSRs can be named or given by address. `.globl`, `.global`, `.text` and
+/// `.section` are accepted and ignored.
+pub fn assemble(source: &str, base: u32) -> Result<Program, AsmError> {
+    let mut statements = Vec::new();
+    let mut labels = HashMap::new();
+    let mut pc = base;
+
+    for (index, raw) in source.lines().enumerate() {
+        let line = index + 1;
+        let mut text = raw.split('#').next().unwrap_or("").trim();
+
+        while let Some((label, rest)) = split_label(text) {
+            if labels.insert(label.to_string(), pc).is_some() {
+                return Err(AsmError {
+                    line,
+                    message: format!("duplicate label `{label}`"),
+                });
+            }
+            text = rest;
+        }
+
+        if text.is_empty() {
+            continue;
+        }
+
+        let (mnemonic, rest) = text.split_once(char::is_whitespace).unwrap_or((text, ""));
+        let operands = rest
+            .split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .collect();
+
+        let statement = Statement {
+            line,
+            mnemonic,
+            operands,
+        };
+
+        pc = pc.wrapping_add(
+            statement_size(&statement).map_err(|message| AsmError { line, message })?,
+        );
+        statements.push(statement);
+    }
+
+    let mut words = Vec::new();
+    for statement in &statements {
+        let pc = base.wrapping_add(4 * words.len() as u32);
+        let encoded = encode_statement(statement, pc, &labels).map_err(|message| AsmError {
+            line: statement.line,
+            message,
+        })?;
+        words.extend(encoded);
+    }
+
+    Ok(Program {
+        base,
+        words,
+        labels,
+    })
+}
+
+fn split_label(text: &str) -> Option<(&str, &str)> {
+    let (label, rest) = text.split_once(':')?;
+    let label = label.trim();
+    let valid = !label.is_empty()
+        && label
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.');
+    valid.then_some((label, rest.trim()))
+}
+
+fn statement_size(statement: &Statement) -> Result<u32, String> {
+    match statement.mnemonic {
+        ".globl" | ".global" | ".text" | ".section" => Ok(0),
+        ".word" => Ok(4 * statement.operands.len() as u32),
+        "li" => {
+            let imm = immediate(operand(&statement.operands, 1)?)?;
+            Ok(4 * li_sequence(0, imm).len() as u32)
+        }
+        "la" => Ok(8),
+        _ => Ok(4),
+    }
+}
+
+fn operand<'a>(operands: &[&'a str], index: usize) -> Result<&'a str, String> {
+    operands
+        .get(index)
+        .copied()
+        .ok_or_else(|| format!("missing operand {}", index + 1))
+}
+
+fn expect_operands(operands: &[&str], count: usize) -> Result<(), String> {
+    if operands.len() != count {
+        return Err(format!(
+            "expected {count} operands, found {}",
+            operands.len()
+        ));
+    }
+    Ok(())
+}
+
+fn register(name: &str) -> Result<u8, String> {
+    parse_register(name).ok_or_else(|| format!("unknown register `{name}`"))
+}
+
+fn immediate(text: &str) -> Result<i64, String> {
+    let (negative, digits) = match text.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, text),
+    };
+
+    let value = if let Some(hex) = digits.strip_prefix("0x") {
+        i64::from_str_radix(hex, 16)
+    } else if let Some(bin) = digits.strip_prefix("0b") {
+        i64::from_str_radix(bin, 2)
+    } else {
+        digits.parse()
+    }
+    .map_err(|_| format!("invalid immediate `{text}`"))?;
+
+    Ok(if negative { -value } else { value })
+}
+
+fn signed_immediate(text: &str, bits: u32) -> Result<i32, String> {
+    let value = immediate(text)?;
+    let limit = 1i64 << (bits - 1);
+    if !(-limit..limit).contains(&value) {
+        return Err(format!(
+            "immediate {value} does not fit in {bits} signed bits"
+        ));
+    }
+    Ok(value as i32)
+}
+
+/// Accepts either a label or a literal offset.
+fn target(text: &str, pc: u32, labels: &HashMap<String, u32>) -> Result<i64, String> {
+    match labels.get(text) {
+        Some(&address) => Ok(address.wrapping_sub(pc) as i32 as i64),
+        None => immediate(text).map_err(|_| format!("unknown label `{text}`")),
+    }
+}
+
+/// A `bits`-wide, even pc-relative offset for `mnemonic`, which names the
+/// instruction in range errors.
+fn offset(
+    text: &str,
+    pc: u32,
+    labels: &HashMap<String, u32>,
+    mnemonic: &str,
+    bits: u32,
+) -> Result<i32, String> {
+    let value = target(text, pc, labels)?;
+    let limit = 1i64 << (bits - 1);
+    if value % 2 != 0 || !(-limit..limit).contains(&value) {
+        return Err(format!(
+            "`{mnemonic}` offset {value} out of range, expected an even value in {}..{limit}",
+            -limit
+        ));
+    }
+    Ok(value as i32)
+}
+
+/// Parses a fence ordering set such as `iorw` or `rw`.
+fn fence_set(text: &str) -> Result<u8, String> {
+    if text == "0" {
+        return Ok(0);
+    }
+    text.chars().try_fold(0u8, |set, c| {
+        let bit = match c {
+            'i' => 0b1000,
+            'o' => 0b0100,
+            'r' => 0b0010,
+            'w' => 0b0001,
+            _ => return Err(format!("invalid fence set `{text}`")),
+        };
+        Ok(set | bit)
+    })
+}
+
+/// Accepts a CSR name such as `mstatus` or a 12-bit address.
+fn csr_address(text: &str) -> Result<i32, String> {
+    let address = match csr::parse(text) {
+        Some(address) => address as i64,
+        None => immediate(text).map_err(|_| format!("unknown CSR `{text}`"))?,
+    };
+    if !(0..0x1000).contains(&address) {
+        return Err(format!("CSR address {address:#x} out of range"));
+    }
+    // `decode` sign-extends the I-type immediate the address lives in.
+    Ok(((address as i32) << 20) >> 20)
+}
+
+/// Source operand of a CSR instruction: a register, or a 5-bit immediate for
+/// the `i` forms, which reuse the rs1 field.
+fn csr_source(text: &str, funct3: u8) -> Result<u8, String> {
+    if funct3 & 0b100 == 0 {
+        return register(text);
+    }
+    let uimm = immediate(text)?;
+    if !(0..32).contains(&uimm) {
+        return Err(format!("CSR immediate {uimm} out of range"));
+    }
+    Ok(uimm as u8)
+}
+
+/// Parses `imm(reg)`.
+fn memory_operand(text: &str) -> Result<(i32, u8), String> {
+    let (imm, rest) = text
+        .split_once('(')
+        .ok_or_else(|| format!("expected `offset(register)`, found `{text}`"))?;
+    let reg = rest
+        .strip_suffix(')')
+        .ok_or_else(|| format!("expected `offset(register)`, found `{text}`"))?;
+    let imm = if imm.trim().is_empty() {
+        0
+    } else {
+        signed_immediate(imm.trim(), 12)?
+    };
+    Ok((imm, register(reg.trim())?))
+}
+
+/// Splits a 32-bit value into `lui` and `addi` parts, where `addi` sign-extends.
+fn hi_lo(value: i32) -> (i32, i32) {
+    let lo = (value << 20) >> 20;
+    let hi = value.wrapping_sub(lo);
+    (hi, lo)
+}
+
+fn li_sequence(rd: u8, imm: i64) -> Vec<DecodedInstruction> {
+    let value = imm as i32;
+    if (-2048..2048).contains(&value) {
+        return vec![i_type(OP_IMM, rd, 0b000, 0, value)];
+    }
+
+    let (hi, lo) = hi_lo(value);
+    let mut sequence = vec![DecodedInstruction::U {
+        op: OP_LUI,
+        rd,
+        imm: hi,
+    }];
+    if lo != 0 {
+        sequence.push(i_type(OP_IMM, rd, 0b000, rd, lo));
+    }
+    sequence
+}
+
+fn i_type(op: u8, rd: u8, funct3: u8, rs1: u8, imm: i32) -> DecodedInstruction {
+    DecodedInstruction::I {
+        op,
+        rd,
+        funct3,
+        rs1,
+        imm,
+    }
+}
+
+fn r_funct(mnemonic: &str) -> Option<(u8, u8)> {
+    Some(match mnemonic {
+        "add" => (0b000, 0b0000000),
+        "sub" => (0b000, 0b0100000),
+        "sll" => (0b001, 0b0000000),
+        "slt" => (0b010, 0b0000000),
+        "sltu" => (0b011, 0b0000000),
+        "xor" => (0b100, 0b0000000),
+        "srl" => (0b101, 0b0000000),
+        "sra" => (0b101, 0b0100000),
+        "or" => (0b110, 0b0000000),
+        "and" => (0b111, 0b0000000),
+        "mul" => (0b000, 0b0000001),
+        "mulh" => (0b001, 0b0000001),
+        "mulhsu" => (0b010, 0b0000001),
+        "mulhu" => (0b011, 0b0000001),
+        "div" => (0b100, 0b0000001),
+        "divu" => (0b101, 0b0000001),
+        "rem" => (0b110, 0b0000001),
+        "remu" => (0b111, 0b0000001),
+        _ => return None,
+    })
+}
+
+fn imm_funct(mnemonic: &str) -> Option<u8> {
+    Some(match mnemonic {
+        "addi" => 0b000,
+        "slti" => 0b010,
+        "sltiu" => 0b011,
+        "xori" => 0b100,
+        "ori" => 0b110,
+        "andi" => 0b111,
+        _ => return None,
+    })
+}
+
+fn shift_funct(mnemonic: &str) -> Option<(u8, u8)> {
+    Some(match mnemonic {
+        "slli" => (0b001, 0b0000000),
+        "srli" => (0b101, 0b0000000),
+        "srai" => (0b101, 0b0100000),
+        _ => return None,
+    })
+}
+
+fn load_funct(mnemonic: &str) -> Option<u8> {
+    Some(match mnemonic {
+        "lb" => 0b000,
+        "lh" => 0b001,
+        "lw" => 0b010,
+        "lbu" => 0b100,
+        "lhu" => 0b101,
+        _ => return None,
+    })
+}
+
+fn store_funct(mnemonic: &str) -> Option<u8> {
+    Some(match mnemonic {
+        "sb" => 0b000,
+        "sh" => 0b001,
+        "sw" => 0b010,
+        _ => return None,
+    })
+}
+
+fn branch_funct(mnemonic: &str) -> Option<u8> {
+    Some(match mnemonic {
+        "beq" => 0b000,
+        "bne" => 0b001,
+        "blt" => 0b100,
+        "bge" => 0b101,
+        "bltu" => 0b110,
+        "bgeu" => 0b111,
+        _ => return None,
+    })
+}
+
+fn csr_funct(mnemonic: &str) -> Option<u8> {
+    Some(match mnemonic {
+        "csrrw" => 0b001,
+        "csrrs" => 0b010,
+        "csrrc" => 0b011,
+        "csrrwi" => 0b101,
+        "csrrsi" => 0b110,
+        "csrrci" => 0b111,
+        _ => return None,
+    })
+}
+
+/// `csrw`, `csrs`, `csrc` and their `i` forms, which discard the old value.
+fn csr_write_funct(mnemonic: &str) -> Option<u8> {
+    Some(match mnemonic {
+        "csrw" => 0b001,
+        "csrs" => 0b010,
+        "csrc" => 0b011,
+        "csrwi" => 0b101,
+        "csrsi" => 0b110,
+        "csrci" => 0b111,
+        _ => return None,
+    })
+}
+
+fn encode_statement(
+    statement: &Statement,
+    pc: u32,
+    labels: &HashMap<String, u32>,
+) -> Result<Vec<u32>, String> {
+    let ops = &statement.operands;
+    let mnemonic = statement.mnemonic;
+
+    let decoded: Vec<DecodedInstruction> = if let Some((funct3, funct7)) = r_funct(mnemonic) {
+        expect_operands(ops, 3)?;
+        vec![DecodedInstruction::R {
+            op: OP,
+            rd: register(ops[0])?,
+            funct3,
+            rs1: register(ops[1])?,
+            rs2: register(ops[2])?,
+            funct7,
+        }]
+    } else if let Some(funct3) = imm_funct(mnemonic) {
+        expect_operands(ops, 3)?;
+        vec![i_type(
+            OP_IMM,
+            register(ops[0])?,
+            funct3,
+            register(ops[1])?,
+            signed_immediate(ops[2], 12)?,
+        )]
+    } else if let Some((funct3, funct7)) = shift_funct(mnemonic) {
+        expect_operands(ops, 3)?;
+        let shamt = immediate(ops[2])?;
+        if !(0..32).contains(&shamt) {
+            return Err(format!("shift amount {shamt} out of range"));
+        }
+        vec![i_type(
+            OP_IMM,
+            register(ops[0])?,
+            funct3,
+            register(ops[1])?,
+            (funct7 as i32) << 5 | shamt as i32,
+        )]
+    } else if let Some(funct3) = load_funct(mnemonic) {
+        expect_operands(ops, 2)?;
+        let (imm, rs1) = memory_operand(ops[1])?;
+        vec![i_type(OP_LOAD, register(ops[0])?, funct3, rs1, imm)]
+    } else if let Some(funct3) = store_funct(mnemonic) {
+        expect_operands(ops, 2)?;
+        let (imm, rs1) = memory_operand(ops[1])?;
+        vec![DecodedInstruction::S {
+            op: OP_STORE,
+            funct3,
+            rs1,
+            rs2: register(ops[0])?,
+            imm,
+        }]
+    } else if let Some(funct3) = branch_funct(mnemonic) {
+        expect_operands(ops, 3)?;
+        vec![DecodedInstruction::B {
+            op: OP_BRANCH,
+            funct3,
+            rs1: register(ops[0])?,
+            rs2: register(ops[1])?,
+            imm: offset(ops[2], pc, labels, mnemonic, 13)?,
+        }]
+    } else if let Some(funct3) = csr_funct(mnemonic) {
+        expect_operands(ops, 3)?;
+        vec![i_type(
+            OP_SYSTEM,
+            register(ops[0])?,
+            funct3,
+            csr_source(ops[2], funct3)?,
+            csr_address(ops[1])?,
+        )]
+    } else if let Some(funct3) = csr_write_funct(mnemonic) {
+        expect_operands(ops, 2)?;
+        vec![i_type(
+            OP_SYSTEM,
+            0,
+            funct3,
+            csr_source(ops[1], funct3)?,
+            csr_address(ops[0])?,
+        )]
+    } else {
+        match mnemonic {
+            ".globl" | ".global" | ".text" | ".section" => vec![],
+            ".word" => {
+                return ops
+                    .iter()
+                    .map(|op| {
+                        let value = immediate(op)?;
+                        if !(i32::MIN as i64..=u32::MAX as i64).contains(&value) {
+                            return Err(format!("word {value} does not fit in 32 bits"));
+                        }
+                        Ok(value as u32)
+                    })
+                    .collect();
+            }
+            "lui" | "auipc" => {
+                expect_operands(ops, 2)?;
+                let imm = immediate(ops[1])?;
+                if !(0..=0xfffff).contains(&imm) {
+                    return Err(format!("upper immediate {imm} out of range"));
+                }
+                vec![DecodedInstruction::U {
+                    op: if mnemonic == "lui" { OP_LUI } else { OP_AUIPC },
+                    rd: register(ops[0])?,
+                    imm: (imm as i32) << 12,
+                }]
+            }
+            "jal" => {
+                let (rd, label) = match ops.len() {
+                    1 => (1, ops[0]),
+                    2 => (register(ops[0])?, ops[1]),
+                    n => return Err(format!("expected 1 or 2 operands, found {n}")),
+                };
+                vec![DecodedInstruction::J {
+                    op: OP_JAL,
+                    rd,
+                    imm: offset(label, pc, labels, mnemonic, 21)?,
+                }]
+            }
+            "jalr" => {
+                let (rd, rs1, imm) = match ops.len() {
+                    1 => (1, register(ops[0])?, 0),
+                    2 => {
+                        let (imm, rs1) = memory_operand(ops[1])?;
+                        (register(ops[0])?, rs1, imm)
+                    }
+                    3 => (
+                        register(ops[0])?,
+                        register(ops[1])?,
+                        signed_immediate(ops[2], 12)?,
+                    ),
+                    n => return Err(format!("expected 1 to 3 operands, found {n}")),
+                };
+                vec![i_type(OP_JALR, rd, 0b000, rs1, imm)]
+            }
+            "fence" => {
+                let (pred, succ) = match ops.len() {
+                    0 => (0b1111, 0b1111),
+                    2 => (fence_set(ops[0])?, fence_set(ops[1])?),
+                    n => return Err(format!("expected 0 or 2 operands, found {n}")),
+                };
+                vec![i_type(OP_MISC_MEM, 0, 0b000, 0, (pred << 4 | succ) as i32)]
+            }
+            "ecall" => {
+                expect_operands(ops, 0)?;
+                vec![i_type(OP_SYSTEM, 0, 0b000, 0, 0)]
+            }
+            "ebreak" => {
+                expect_operands(ops, 0)?;
+                vec![i_type(OP_SYSTEM, 0, 0b000, 0, 1)]
+            }
+            "mret" => {
+                expect_operands(ops, 0)?;
+                vec![i_type(OP_SYSTEM, 0, 0b000, 0, 0x302)]
+            }
+
+            "nop" => {
+                expect_operands(ops, 0)?;
+                vec![i_type(OP_IMM, 0, 0b000, 0, 0)]
+            }
+            "li" => {
+                expect_operands(ops, 2)?;
+                let imm = immediate(ops[1])?;
+                if !(i32::MIN as i64..=u32::MAX as i64).contains(&imm) {
+                    return Err(format!("immediate {imm} does not fit in 32 bits"));
+                }
+                li_sequence(register(ops[0])?, imm)
+            }
+            "la" => {
+                expect_operands(ops, 2)?;
+                let rd = register(ops[0])?;
+                let address = labels
+                    .get(ops[1])
+                    .ok_or_else(|| format!("unknown label `{}`", ops[1]))?;
+                let (hi, lo) = hi_lo(address.wrapping_sub(pc) as i32);
+                vec![
+                    DecodedInstruction::U {
+                        op: OP_AUIPC,
+                        rd,
+                        imm: hi,
+                    },
+                    i_type(OP_IMM, rd, 0b000, rd, lo),
+                ]
+            }
+            "mv" => {
+                expect_operands(ops, 2)?;
+                vec![i_type(
+                    OP_IMM,
+                    register(ops[0])?,
+                    0b000,
+                    register(ops[1])?,
+                    0,
+                )]
+            }
+            "j" => {
+                expect_operands(ops, 1)?;
+                vec![DecodedInstruction::J {
+                    op: OP_JAL,
+                    rd: 0,
+                    imm: offset(ops[0], pc, labels, mnemonic, 21)?,
+                }]
+            }
+            "jr" => {
+                expect_operands(ops, 1)?;
+                vec![i_type(OP_JALR, 0, 0b000, register(ops[0])?, 0)]
+            }
+            "ret" => {
+                expect_operands(ops, 0)?;
+                vec![i_type(OP_JALR, 0, 0b000, 1, 0)]
+            }
+            "csrr" => {
+                expect_operands(ops, 2)?;
+                vec![i_type(
+                    OP_SYSTEM,
+                    register(ops[0])?,
+                    0b010,
+                    0,
+                    csr_address(ops[1])?,
+                )]
+            }
+            "beqz" | "bnez" => {
+                expect_operands(ops, 2)?;
+                vec![DecodedInstruction::B {
+                    op: OP_BRANCH,
+                    funct3: if mnemonic == "beqz" { 0b000 } else { 0b001 },
+                    rs1: register(ops[0])?,
+                    rs2: 0,
+                    imm: offset(ops[1], pc, labels, mnemonic, 13)?,
+                }]
+            }
+            _ => return Err(format!("unknown instruction `{mnemonic}`")),
+        }
+    };
+
+    Ok(decoded.into_iter().map(encode).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(source: &str) -> AsmError {
+        assemble(source, 0).expect_err(source)
+    }
+
+    #[test]
+    fn names_the_instruction_in_offset_errors() {
+        let cases = [
+            (
+                "jal ra, 1048576",
+                "`jal` offset 1048576",
+                "-1048576..1048576",
+            ),
+            ("j -1048578", "`j` offset -1048578", "-1048576..1048576"),
+            ("beq a0, a1, 4096", "`beq` offset 4096", "-4096..4096"),
+            ("bnez a0, -4098", "`bnez` offset -4098", "-4096..4096"),
+            ("bltu a0, a1, 3", "`bltu` offset 3", "-4096..4096"),
+        ];
+
+        for (source, offset, range) in cases {
+            let message = error(source).message;
+            assert!(message.starts_with(offset), "{source}: {message}");
+            assert!(message.ends_with(range), "{source}: {message}");
+        }
+    }
+
+    #[test]
+    fn accepts_offsets_at_the_range_edges() {
+        for source in [
+            "jal ra, 1048574",
+            "j -1048576",
+            "beq a0, a1, 4094",
+            "beqz a0, -4096",
+        ] {
+            assert!(assemble(source, 0).is_ok(), "{source}");
+        }
+    }
+
+    #[test]
+    fn checks_the_range_of_label_offsets() {
+        let far = format!("beq a0, a1, far\n{}far:\n", "nop\n".repeat(1023));
+        let err = error(&far);
+        assert_eq!(err.line, 1);
+        assert!(
+            err.message.starts_with("`beq` offset 4096"),
+            "{}",
+            err.message
+        );
+
+        let near = format!("j far\n{}far:\n", "nop\n".repeat(1024));
+        assert!(assemble(&near, 0).is_ok());
+    }
+}
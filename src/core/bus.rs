@@ -0,0 +1,279 @@
+use std::io::Write;
+
+use crate::{
+    core::cpu::Trap,
+    neuro::{network::Network, neuron::NeuronId},
+};
+
+/// Device windows, laid out like QEMU's `virt` machine so bare-metal code
+/// written for it runs unchanged.
+pub const FINISHER_BASE: u32 = 0x0010_0000;
+pub const UART_BASE: u32 = 0x1000_0000;
+pub const SPIKE_BASE: u32 = 0x1000_1000;
+const WINDOW_SIZE: u32 = 0x1000;
+
+/// A memory-mapped device. Offsets are relative to the device's base, and
+/// `None` turns the access into an access fault.
+pub trait Device {
+    fn load(&mut self, offset: u32, len: usize) -> Option<u32>;
+    fn store(&mut self, offset: u32, len: usize, value: u32) -> Option<()>;
+}
+
+/// The 16550 subset needed to print: writes to THR go out and LSR always
+/// reports an empty transmitter. There is no input.
+#[derive(Debug)]
+pub struct Uart {
+    /// When set, output is collected here instead of going to stdout.
+    pub capture: Option<Vec<u8>>,
+}
+
+const UART_THR: u32 = 0;
+const UART_LSR: u32 = 5;
+const UART_LSR_THRE_TEMT: u32 = 0x60;
+
+impl Device for Uart {
+    fn load(&mut self, offset: u32, _len: usize) -> Option<u32> {
+        match offset {
+            UART_LSR => Some(UART_LSR_THRE_TEMT),
+            0..8 => Some(0),
+            _ => None,
+        }
+    }
+
+    fn store(&mut self, offset: u32, _len: usize, value: u32) -> Option<()> {
+        match offset {
+            UART_THR => {
+                let byte = value as u8;
+                match &mut self.capture {
+                    Some(buffer) => buffer.push(byte),
+                    None => {
+                        let mut stdout = std::io::stdout();
+                        stdout.write_all(&[byte]).ok()?;
+                        if byte == b'\n' {
+                            stdout.flush().ok()?;
+                        }
+                    }
+                }
+                Some(())
+            }
+            1..8 => Some(()),
+            _ => None,
+        }
+    }
+}
+
+/// SiFive test finisher: writing `0x5555` powers off with status 0 and
+/// `status << 16 | 0x3333` with `status`.
+#[derive(Debug, Default)]
+pub struct Finisher {
+    pub exit: Option<u32>,
+}
+
+const FINISHER_FAIL: u32 = 0x3333;
+const FINISHER_PASS: u32 = 0x5555;
+
+impl Device for Finisher {
+    fn load(&mut self, offset: u32, _len: usize) -> Option<u32> {
+        (offset == 0).then_some(0)
+    }
+
+    fn store(&mut self, offset: u32, _len: usize, value: u32) -> Option<()> {
+        if offset != 0 {
+            return None;
+        }
+        match value & 0xffff {
+            FINISHER_PASS => self.exit = Some(0),
+            FINISHER_FAIL => self.exit = Some(value >> 16),
+            _ => {}
+        }
+        Some(())
+    }
+}
+
+/// A neuron the guest can reach through the spike device.
+#[derive(Clone, Debug)]
+pub struct SpikePort {
+    pub name: String,
+    pub neuron: NeuronId,
+    /// Weight of each spike the guest injects.
+    pub weight: f64,
+    /// Spikes the neuron has fired since the count was last cleared.
+    pub count: u32,
+}
+
+/// Connects a guest to a `Network`. The guest selects a port, injects spikes
+/// into its neuron, advances the network and reads back spike counts.
+///
+/// | offset | register                                                |
+/// |--------|---------------------------------------------------------|
+/// | 0x00   | SELECT: port index                                      |
+/// | 0x04   | INJECT: any write schedules one spike on the port       |
+/// | 0x08   | COUNT: spikes fired by the port's neuron, writable      |
+/// | 0x0c   | TICK: writing n runs n ticks, reads the low time word   |
+/// | 0x10   | PORTS: number of ports, read-only                       |
+/// | 0x14   | TICK_HI: high word of the network time, read-only       |
+/// | 0x100  | NAME: the port's name, read-only, up to 0x1ff           |
+///
+/// NAME holds the selected port's name as bytes, zero-padded, so a guest
+/// finds a neuron by name by comparing NAME against it for each port. Names
+/// longer than 255 bytes are cut short, keeping the last byte zero.
+///
+/// Writing more than `SPIKE_MAX_TICKS` to TICK is a store access fault, so
+/// one store cannot stall the emulator for billions of ticks. TICK wraps
+/// after 2^32 ticks; read TICK_HI, TICK, TICK_HI again, like `cycleh`, when
+/// the full time is needed.
+#[derive(Debug)]
+pub struct SpikeDevice {
+    pub network: Network,
+    pub dt: f64,
+    pub ports: Vec<SpikePort>,
+    selected: usize,
+}
+
+const SPIKE_SELECT: u32 = 0x00;
+const SPIKE_INJECT: u32 = 0x04;
+const SPIKE_COUNT: u32 = 0x08;
+const SPIKE_TICK: u32 = 0x0c;
+const SPIKE_PORTS: u32 = 0x10;
+const SPIKE_TICK_HI: u32 = 0x14;
+const SPIKE_NAME: u32 = 0x100;
+const SPIKE_NAME_END: u32 = 0x1ff;
+
+/// Most ticks a single write to TICK may run.
+pub const SPIKE_MAX_TICKS: u32 = 1 << 16;
+
+impl SpikeDevice {
+    pub fn new(mut network: Network, dt: f64, ports: Vec<SpikePort>) -> Self {
+        network.resize_events();
+        Self {
+            network,
+            dt,
+            ports,
+            selected: 0,
+        }
+    }
+
+    /// `len` bytes of the selected port's name from `index`, little-endian.
+    fn name_bytes(&self, index: usize, len: usize) -> Option<u32> {
+        let name = self.ports.get(self.selected)?.name.as_bytes();
+        let name = &name[..name.len().min((SPIKE_NAME_END - SPIKE_NAME) as usize)];
+        Some(
+            (index..index + len)
+                .rev()
+                .map(|i| name.get(i).copied().unwrap_or(0))
+                .fold(0, |word, byte| word << 8 | byte as u32),
+        )
+    }
+
+    pub fn tick(&mut self) {
+        self.network.tick(self.dt);
+
+        for port in &mut self.ports {
+            if self.network.spiked.contains(&port.neuron) {
+                port.count += 1;
+            }
+        }
+    }
+}
+
+impl Device for SpikeDevice {
+    fn load(&mut self, offset: u32, len: usize) -> Option<u32> {
+        match offset {
+            SPIKE_SELECT => Some(self.selected as u32),
+            SPIKE_INJECT => Some(0),
+            SPIKE_COUNT => Some(self.ports.get(self.selected)?.count),
+            SPIKE_TICK => Some(self.network.t as u32),
+            SPIKE_PORTS => Some(self.ports.len() as u32),
+            SPIKE_TICK_HI => Some((self.network.t as u64 >> 32) as u32),
+            SPIKE_NAME..=SPIKE_NAME_END => self.name_bytes((offset - SPIKE_NAME) as usize, len),
+            _ => None,
+        }
+    }
+
+    fn store(&mut self, offset: u32, _len: usize, value: u32) -> Option<()> {
+        match offset {
+            SPIKE_SELECT => self.selected = value as usize,
+            SPIKE_INJECT => {
+                let port = self.ports.get(self.selected)?;
+                self.network.schedule_spike(port.neuron, port.weight, 0);
+            }
+            SPIKE_COUNT => self.ports.get_mut(self.selected)?.count = value,
+            SPIKE_TICK if value <= SPIKE_MAX_TICKS => (0..value).for_each(|_| self.tick()),
+            _ => return None,
+        }
+        Some(())
+    }
+}
+
+/// Devices mapped next to RAM.
+#[derive(Debug)]
+pub struct Bus {
+    pub uart: Uart,
+    pub finisher: Finisher,
+    pub spikes: Option<SpikeDevice>,
+}
+
+impl Default for Bus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Bus {
+    pub fn new() -> Self {
+        Self {
+            uart: Uart { capture: None },
+            finisher: Finisher::default(),
+            spikes: None,
+        }
+    }
+
+    fn device(&mut self, addr: u32) -> Option<(&mut dyn Device, u32)> {
+        let base = addr & !(WINDOW_SIZE - 1);
+        let device: &mut dyn Device = match base {
+            FINISHER_BASE => &mut self.finisher,
+            UART_BASE => &mut self.uart,
+            SPIKE_BASE => self.spikes.as_mut()?,
+            _ => return None,
+        };
+        Some((device, addr - base))
+    }
+
+    /// Returns `None` when no device is mapped at `addr`.
+    pub fn load(&mut self, addr: u32, len: usize) -> Option<Result<u32, Trap>> {
+        let (device, offset) = self.device(addr)?;
+        if !addr.is_multiple_of(len as u32) {
+            return Some(Err(Trap::LoadAddressMisaligned(addr)));
+        }
+        Some(device.load(offset, len).ok_or(Trap::LoadAccessFault(addr)))
+    }
+
+    /// Returns `None` when no device is mapped at `addr`.
+    pub fn store(&mut self, addr: u32, len: usize, value: u32) -> Option<Result<(), Trap>> {
+        let (device, offset) = self.device(addr)?;
+        if !addr.is_multiple_of(len as u32) {
+            return Some(Err(Trap::StoreAddressMisaligned(addr)));
+        }
+        Some(
+            device
+                .store(offset, len, value)
+                .ok_or(Trap::StoreAccessFault(addr)),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_the_network_time_across_tick_registers() {
+        let mut device = SpikeDevice::new(Network::new(), 0.1, Vec::new());
+        device.network.t = (3 << 32) + 5;
+
+        assert_eq!(device.load(SPIKE_TICK, 4), Some(5));
+        assert_eq!(device.load(SPIKE_TICK_HI, 4), Some(3));
+        assert_eq!(device.store(SPIKE_TICK_HI, 4, 0), None);
+        assert_eq!(device.store(SPIKE_TICK, 4, SPIKE_MAX_TICKS + 1), None);
+    }
+}
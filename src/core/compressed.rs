@@ -0,0 +1,303 @@
+use anyhow::bail;
+
+use crate::core::instructions::Instruction;
+
+/// 32-bit instructions have both low bits set; anything else is a 16-bit
+/// RV32C instruction.
+pub fn is_compressed(word: u32) -> bool {
+    word & 0b11 != 0b11
+}
+
+/// Bits `hi..=lo` of `half`, shifted down to bit 0.
+fn bits(half: u16, hi: u32, lo: u32) -> u32 {
+    (half as u32 >> lo) & ((1 << (hi - lo + 1)) - 1)
+}
+
+/// Moves bits `hi..=lo` of `half` so the field starts at bit `to`.
+fn place(half: u16, hi: u32, lo: u32, to: u32) -> u32 {
+    bits(half, hi, lo) << to
+}
+
+fn sign_extend(value: u32, width: u32) -> i32 {
+    let shift = 32 - width;
+    ((value << shift) as i32) >> shift
+}
+
+/// Registers x8-x15, encoded in three bits by most quadrant 0 and 1 forms.
+fn creg(half: u16, lo: u32) -> u8 {
+    8 + bits(half, lo + 2, lo) as u8
+}
+
+/// Expands a 16-bit instruction into the 32-bit instruction it stands for.
+/// Floating-point loads and stores are rejected, as are reserved encodings
+/// and the RV64/RV128-only forms.
+pub fn expand(half: u16) -> anyhow::Result<Instruction> {
+    let quadrant = half & 0b11;
+    let funct3 = bits(half, 15, 13);
+
+    let rd = bits(half, 11, 7) as u8;
+    let rs2 = bits(half, 6, 2) as u8;
+    let imm6 = sign_extend(place(half, 12, 12, 5) | bits(half, 6, 2), 6);
+
+    let instruction = match (quadrant, funct3) {
+        (0b00, 0b000) => {
+            let imm = place(half, 12, 11, 4)
+                | place(half, 10, 7, 6)
+                | place(half, 6, 6, 2)
+                | place(half, 5, 5, 3);
+            if imm == 0 {
+                bail!("Reserved compressed instruction {half:#06x}");
+            }
+            Instruction::Addi {
+                rd: creg(half, 2),
+                rs1: 2,
+                imm: imm as i32,
+            }
+        }
+        (0b00, 0b010) => Instruction::Lw {
+            rd: creg(half, 2),
+            rs1: creg(half, 7),
+            imm: (place(half, 12, 10, 3) | place(half, 6, 6, 2) | place(half, 5, 5, 6)) as i32,
+        },
+        (0b00, 0b110) => Instruction::Sw {
+            rs1: creg(half, 7),
+            rs2: creg(half, 2),
+            imm: (place(half, 12, 10, 3) | place(half, 6, 6, 2) | place(half, 5, 5, 6)) as i32,
+        },
+
+        (0b01, 0b000) => Instruction::Addi {
+            rd,
+            rs1: rd,
+            imm: imm6,
+        },
+        (0b01, 0b001) => Instruction::Jal {
+            rd: 1,
+            imm: jump_offset(half),
+        },
+        (0b01, 0b010) => Instruction::Addi {
+            rd,
+            rs1: 0,
+            imm: imm6,
+        },
+        (0b01, 0b011) if rd == 2 => {
+            let imm = sign_extend(
+                place(half, 12, 12, 9)
+                    | place(half, 6, 6, 4)
+                    | place(half, 5, 5, 6)
+                    | place(half, 4, 3, 7)
+                    | place(half, 2, 2, 5),
+                10,
+            );
+            if imm == 0 {
+                bail!("Reserved compressed instruction {half:#06x}");
+            }
+            Instruction::Addi { rd, rs1: rd, imm }
+        }
+        (0b01, 0b011) => {
+            if imm6 == 0 {
+                bail!("Reserved compressed instruction {half:#06x}");
+            }
+            Instruction::Lui {
+                rd,
+                imm: imm6 << 12,
+            }
+        }
+        (0b01, 0b100) => {
+            let rd = creg(half, 7);
+            let rs2 = creg(half, 2);
+
+            match bits(half, 11, 10) {
+                0b00 | 0b01 if bits(half, 12, 12) != 0 => {
+                    bail!("Compressed shift amount {half:#06x} exceeds XLEN")
+                }
+                0b00 => Instruction::Srli {
+                    rd,
+                    rs1: rd,
+                    shamt: imm6 as u8 & 0x1f,
+                },
+                0b01 => Instruction::Srai {
+                    rd,
+                    rs1: rd,
+                    shamt: imm6 as u8 & 0x1f,
+                },
+                0b10 => Instruction::Andi {
+                    rd,
+                    rs1: rd,
+                    imm: imm6,
+                },
+                _ => match (bits(half, 12, 12), bits(half, 6, 5)) {
+                    (0, 0b00) => Instruction::Sub { rd, rs1: rd, rs2 },
+                    (0, 0b01) => Instruction::Xor { rd, rs1: rd, rs2 },
+                    (0, 0b10) => Instruction::Or { rd, rs1: rd, rs2 },
+                    (0, 0b11) => Instruction::And { rd, rs1: rd, rs2 },
+                    _ => bail!("Unsupported compressed instruction {half:#06x}"),
+                },
+            }
+        }
+        (0b01, 0b101) => Instruction::Jal {
+            rd: 0,
+            imm: jump_offset(half),
+        },
+        (0b01, 0b110) | (0b01, 0b111) => {
+            let rs1 = creg(half, 7);
+            let imm = sign_extend(
+                place(half, 12, 12, 8)
+                    | place(half, 11, 10, 3)
+                    | place(half, 6, 5, 6)
+                    | place(half, 4, 3, 1)
+                    | place(half, 2, 2, 5),
+                9,
+            );
+
+            if funct3 == 0b110 {
+                Instruction::Beq { rs1, rs2: 0, imm }
+            } else {
+                Instruction::Bne { rs1, rs2: 0, imm }
+            }
+        }
+
+        (0b10, 0b000) => {
+            if bits(half, 12, 12) != 0 {
+                bail!("Compressed shift amount {half:#06x} exceeds XLEN");
+            }
+            Instruction::Slli {
+                rd,
+                rs1: rd,
+                shamt: rs2,
+            }
+        }
+        (0b10, 0b010) => {
+            if rd == 0 {
+                bail!("Reserved compressed instruction {half:#06x}");
+            }
+            Instruction::Lw {
+                rd,
+                rs1: 2,
+                imm: (place(half, 12, 12, 5) | place(half, 6, 4, 2) | place(half, 3, 2, 6)) as i32,
+            }
+        }
+        (0b10, 0b100) => match (bits(half, 12, 12), rd, rs2) {
+            (0, 0, 0) => bail!("Reserved compressed instruction {half:#06x}"),
+            (0, rs1, 0) => Instruction::Jalr { rd: 0, rs1, imm: 0 },
+            (0, rd, rs2) => Instruction::Add { rd, rs1: 0, rs2 },
+            (_, 0, 0) => Instruction::Ebreak,
+            (_, rs1, 0) => Instruction::Jalr { rd: 1, rs1, imm: 0 },
+            (_, rd, rs2) => Instruction::Add { rd, rs1: rd, rs2 },
+        },
+        (0b10, 0b110) => Instruction::Sw {
+            rs1: 2,
+            rs2,
+            imm: (place(half, 12, 9, 2) | place(half, 8, 7, 6)) as i32,
+        },
+
+        _ => bail!("Unsupported compressed instruction {half:#06x}"),
+    };
+
+    Ok(instruction)
+}
+
+/// Offset of `c.j` and `c.jal`.
+fn jump_offset(half: u16) -> i32 {
+    sign_extend(
+        place(half, 12, 12, 11)
+            | place(half, 11, 11, 4)
+            | place(half, 10, 9, 8)
+            | place(half, 8, 8, 10)
+            | place(half, 7, 7, 6)
+            | place(half, 6, 6, 7)
+            | place(half, 5, 3, 1)
+            | place(half, 2, 2, 5),
+        12,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::decoder::encode_instruction;
+
+    /// Each compressed instruction and its 32-bit expansion, from `llvm-mc
+    /// -triple=riscv32 -mattr=+c -show-encoding` and the same instruction
+    /// written out in full.
+    #[test]
+    fn expands_every_quadrant() {
+        let cases = [
+            (0x0040, 0x0041_0413), // c.addi4spn s0, sp, 4
+            (0x1ffc, 0x3fc1_0793), // c.addi4spn a5, sp, 1020
+            (0x4188, 0x0005_a503), // c.lw a0, 0(a1)
+            (0x5de8, 0x07c5_a503), // c.lw a0, 124(a1)
+            (0xc0b0, 0x04c4_a023), // c.sw a2, 64(s1)
+            (0xdcf0, 0x06c4_ae23), // c.sw a2, 124(s1)
+            (0x0001, 0x0000_0013), // c.nop
+            (0x1501, 0xfe05_0513), // c.addi a0, -32
+            (0x057d, 0x01f5_0513), // c.addi a0, 31
+            (0x2009, 0x0020_00ef), // c.jal 2
+            (0x3001, 0x801f_f0ef), // c.jal -2048
+            (0x2ffd, 0x7fe0_00ef), // c.jal 2046
+            (0x2101, 0x4000_00ef), // c.jal 0x400
+            (0x52fd, 0xfff0_0293), // c.li t0, -1
+            (0x42fd, 0x01f0_0293), // c.li t0, 31
+            (0x7101, 0xe001_0113), // c.addi16sp sp, -512
+            (0x617d, 0x1f01_0113), // c.addi16sp sp, 496
+            (0x6141, 0x0101_0113), // c.addi16sp sp, 16
+            (0x7301, 0xfffe_0337), // c.lui t1, 0xfffe0
+            (0x6305, 0x0000_1337), // c.lui t1, 1
+            (0x637d, 0x0001_f337), // c.lui t1, 31
+            (0x807d, 0x01f4_5413), // c.srli s0, 31
+            (0x8405, 0x4014_5413), // c.srai s0, 1
+            (0x9801, 0xfe04_7413), // c.andi s0, -32
+            (0x8c05, 0x4094_0433), // c.sub s0, s1
+            (0x8d2d, 0x00b5_4533), // c.xor a0, a1
+            (0x8f5d, 0x00f7_6733), // c.or a4, a5
+            (0x8e75, 0x00d6_7633), // c.and a2, a3
+            (0xb001, 0x801f_f06f), // c.j -2048
+            (0xaffd, 0x7fe0_006f), // c.j 2046
+            (0xa081, 0x0400_006f), // c.j 0x40
+            (0xd001, 0xf004_00e3), // c.beqz s0, -256
+            (0xcc7d, 0x0e04_0f63), // c.beqz s0, 254
+            (0xc009, 0x0004_0163), // c.beqz s0, 2
+            (0xfffd, 0xfe07_9fe3), // c.bnez a5, -2
+            (0xe3c1, 0x0807_9063), // c.bnez a5, 0x80
+            (0x057e, 0x01f5_1513), // c.slli a0, 31
+            (0x4082, 0x0001_2083), // c.lwsp ra, 0(sp)
+            (0x50fe, 0x0fc1_2083), // c.lwsp ra, 252(sp)
+            (0x428a, 0x0801_2283), // c.lwsp t0, 128(sp)
+            (0x8082, 0x0000_8067), // c.jr ra
+            (0x852e, 0x00b0_0533), // c.mv a0, a1
+            (0x9002, 0x0010_0073), // c.ebreak
+            (0x9282, 0x0002_80e7), // c.jalr t0
+            (0x952e, 0x00b5_0533), // c.add a0, a1
+            (0xc006, 0x0011_2023), // c.swsp ra, 0(sp)
+            (0xdf86, 0x0e11_2e23), // c.swsp ra, 252(sp)
+            (0xc116, 0x0851_2023), // c.swsp t0, 128(sp)
+        ];
+
+        for (half, word) in cases {
+            assert!(is_compressed(half as u32));
+            let instruction = expand(half).unwrap_or_else(|e| panic!("{half:#06x}: {e}"));
+            assert_eq!(encode_instruction(&instruction), word, "{half:#06x}");
+        }
+    }
+
+    #[test]
+    fn rejects_reserved_and_unsupported_encodings() {
+        let cases = [
+            0x0000, // all zeros, c.addi4spn with nzuimm=0
+            0x0004, // c.addi4spn a1, sp, 0
+            0x6101, // c.addi16sp with nzimm=0
+            0x6301, // c.lui t1 with nzimm=0
+            0x4002, // c.lwsp with rd=x0
+            0x8002, // c.jr with rs1=x0
+            0x9001, // c.srli with shamt[5] set
+            0x9401, // c.srai with shamt[5] set
+            0x1086, // c.slli with shamt[5] set
+            0x9c41, // c.subw, RV64 only
+            0x2000, // c.fld
+            0xe000, // c.fsw
+        ];
+
+        for half in cases {
+            assert!(expand(half).is_err(), "{half:#06x}");
+        }
+    }
+}
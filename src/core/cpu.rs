@@ -0,0 +1,614 @@
+use std::fmt;
+
+use crate::core::{
+    bus::Bus,
+    compressed::is_compressed,
+    csr::{Csrs, MSTATUS, MSTATUS_MIE, MSTATUS_MPIE, MSTATUS_MPP},
+    decoder::{decode_instruction, encode_instruction},
+    hybrid::HybridAlu,
+    instructions::Instruction,
+    spiking_regfile::SpikingRegisterFile,
+    trace::{Commit, Write},
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Trap {
+    InstructionAddressMisaligned(u32),
+    InstructionAccessFault(u32),
+    IllegalInstruction(u32),
+    Breakpoint(u32),
+    LoadAddressMisaligned(u32),
+    LoadAccessFault(u32),
+    StoreAddressMisaligned(u32),
+    StoreAccessFault(u32),
+    EnvironmentCall(u32),
+}
+
+impl fmt::Display for Trap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Trap::InstructionAddressMisaligned(addr) => {
+                write!(f, "instruction address misaligned at {addr:#010x}")
+            }
+            Trap::InstructionAccessFault(addr) => {
+                write!(f, "instruction access fault at {addr:#010x}")
+            }
+            Trap::IllegalInstruction(word) => write!(f, "illegal instruction {word:#010x}"),
+            Trap::Breakpoint(pc) => write!(f, "breakpoint at {pc:#010x}"),
+            Trap::LoadAddressMisaligned(addr) => {
+                write!(f, "load address misaligned at {addr:#010x}")
+            }
+            Trap::LoadAccessFault(addr) => write!(f, "load access fault at {addr:#010x}"),
+            Trap::StoreAddressMisaligned(addr) => {
+                write!(f, "store address misaligned at {addr:#010x}")
+            }
+            Trap::StoreAccessFault(addr) => write!(f, "store access fault at {addr:#010x}"),
+            Trap::EnvironmentCall(pc) => write!(f, "environment call at {pc:#010x}"),
+        }
+    }
+}
+
+impl std::error::Error for Trap {}
+
+impl Trap {
+    /// Exception code written to `mcause`.
+    pub fn cause(&self) -> u32 {
+        match self {
+            Trap::InstructionAddressMisaligned(_) => 0,
+            Trap::InstructionAccessFault(_) => 1,
+            Trap::IllegalInstruction(_) => 2,
+            Trap::Breakpoint(_) => 3,
+            Trap::LoadAddressMisaligned(_) => 4,
+            Trap::LoadAccessFault(_) => 5,
+            Trap::StoreAddressMisaligned(_) => 6,
+            Trap::StoreAccessFault(_) => 7,
+            Trap::EnvironmentCall(_) => 11,
+        }
+    }
+
+    /// Value written to `mtval`: the faulting address or instruction word.
+    pub fn tval(&self) -> u32 {
+        match *self {
+            Trap::EnvironmentCall(_) => 0,
+            Trap::InstructionAddressMisaligned(value)
+            | Trap::InstructionAccessFault(value)
+            | Trap::IllegalInstruction(value)
+            | Trap::Breakpoint(value)
+            | Trap::LoadAddressMisaligned(value)
+            | Trap::LoadAccessFault(value)
+            | Trap::StoreAddressMisaligned(value)
+            | Trap::StoreAccessFault(value) => value,
+        }
+    }
+}
+
+pub struct Memory {
+    pub base: u32,
+    pub data: Vec<u8>,
+}
+
+/// Only the layout: the contents are megabytes of mostly zeros.
+impl fmt::Debug for Memory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Memory")
+            .field("base", &format_args!("{:#010x}", self.base))
+            .field("size", &self.data.len())
+            .finish()
+    }
+}
+
+impl Memory {
+    pub fn new(base: u32, size: usize) -> Self {
+        Self {
+            base,
+            data: vec![0; size],
+        }
+    }
+
+    fn offset(&self, addr: u32, len: usize) -> Option<usize> {
+        let offset = addr.checked_sub(self.base)? as usize;
+        let end = offset.checked_add(len)?;
+        (end <= self.data.len()).then_some(offset)
+    }
+
+    pub fn load(&self, addr: u32, len: usize) -> Result<u32, Trap> {
+        if !addr.is_multiple_of(len as u32) {
+            return Err(Trap::LoadAddressMisaligned(addr));
+        }
+        let offset = self.offset(addr, len).ok_or(Trap::LoadAccessFault(addr))?;
+
+        let mut bytes = [0u8; 4];
+        bytes[..len].copy_from_slice(&self.data[offset..offset + len]);
+        Ok(u32::from_le_bytes(bytes))
+    }
+
+    pub fn store(&mut self, addr: u32, len: usize, value: u32) -> Result<(), Trap> {
+        if !addr.is_multiple_of(len as u32) {
+            return Err(Trap::StoreAddressMisaligned(addr));
+        }
+        let offset = self.offset(addr, len).ok_or(Trap::StoreAccessFault(addr))?;
+
+        self.data[offset..offset + len].copy_from_slice(&value.to_le_bytes()[..len]);
+        Ok(())
+    }
+
+    pub fn read_bytes(&self, addr: u32, len: usize) -> Result<&[u8], Trap> {
+        let offset = self.offset(addr, len).ok_or(Trap::LoadAccessFault(addr))?;
+        Ok(&self.data[offset..offset + len])
+    }
+
+    pub fn write_bytes(&mut self, addr: u32, bytes: &[u8]) -> Result<(), Trap> {
+        let offset = self
+            .offset(addr, bytes.len())
+            .ok_or(Trap::StoreAccessFault(addr))?;
+
+        self.data[offset..offset + bytes.len()].copy_from_slice(bytes);
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct Cpu {
+    pub regs: [u32; 32],
+    pub pc: u32,
+    pub memory: Memory,
+    /// Devices, checked before `memory` on loads and stores.
+    pub bus: Bus,
+    pub csrs: Csrs,
+    /// When set, ALU ops run on spiking circuits instead of the reference ALU.
+    pub hybrid: Option<HybridAlu>,
+    /// When set, register reads and writes go through spiking latches and
+    /// `regs` is only a shadow copy to check reads against.
+    pub spiking_regs: Option<SpikingRegisterFile>,
+    /// Reads from `spiking_regs` that disagreed with `regs`.
+    pub reg_mismatches: u64,
+    /// When set, `step` records each retired instruction in `commit`.
+    pub trace: bool,
+    /// The instruction retired by the last `step`, or `None` if it trapped.
+    pub commit: Option<Commit>,
+}
+
+impl Cpu {
+    pub fn new(memory: Memory) -> Self {
+        let pc = memory.base;
+        Self {
+            regs: [0; 32],
+            pc,
+            memory,
+            bus: Bus::new(),
+            csrs: Csrs::new(),
+            hybrid: None,
+            spiking_regs: None,
+            reg_mismatches: 0,
+            trace: false,
+            commit: None,
+        }
+    }
+
+    pub fn read_reg(&mut self, reg: u8) -> u32 {
+        let expected = self.regs[reg as usize];
+
+        match self.spiking_regs.as_mut() {
+            Some(file) => {
+                let value = file.read(reg);
+                self.reg_mismatches += (value != expected) as u64;
+                value
+            }
+            None => expected,
+        }
+    }
+
+    pub fn write_reg(&mut self, reg: u8, value: u32) {
+        if reg != 0 {
+            self.regs[reg as usize] = value;
+            if let Some(file) = self.spiking_regs.as_mut() {
+                file.write(reg, value);
+            }
+            self.log(Write::Reg(reg, value));
+        }
+    }
+
+    fn log(&mut self, write: Write) {
+        if let Some(commit) = &mut self.commit {
+            commit.writes.push(write);
+        }
+    }
+
+    fn load(&mut self, addr: u32, len: usize) -> Result<u32, Trap> {
+        let value = match self.bus.load(addr, len) {
+            Some(value) => value?,
+            None => self.memory.load(addr, len)?,
+        };
+        if let Some(commit) = &mut self.commit {
+            commit.loads.push(addr);
+        }
+        Ok(value)
+    }
+
+    fn store(&mut self, addr: u32, len: usize, value: u32) -> Result<(), Trap> {
+        match self.bus.store(addr, len, value) {
+            Some(result) => result?,
+            None => self.memory.store(addr, len, value)?,
+        }
+        if let Some(commit) = &mut self.commit {
+            let mask = u32::MAX >> (32 - 8 * len);
+            commit.stores.push((addr, value & mask, len));
+        }
+        Ok(())
+    }
+
+    /// Returns the instruction at pc, zero-extended when it is a 16-bit
+    /// compressed one. The upper half is only fetched when it is needed.
+    pub fn fetch(&self) -> Result<u32, Trap> {
+        if !self.pc.is_multiple_of(2) {
+            return Err(Trap::InstructionAddressMisaligned(self.pc));
+        }
+        let low = self
+            .memory
+            .load(self.pc, 2)
+            .map_err(|_| Trap::InstructionAccessFault(self.pc))?;
+        if is_compressed(low) {
+            return Ok(low);
+        }
+
+        let upper = self.pc.wrapping_add(2);
+        let high = self
+            .memory
+            .load(upper, 2)
+            .map_err(|_| Trap::InstructionAccessFault(upper))?;
+        Ok(high << 16 | low)
+    }
+
+    /// Executes one instruction. A trap is taken through `mtvec` as on
+    /// hardware and also returned, so callers can stop on it.
+    pub fn step(&mut self) -> Result<(), Trap> {
+        self.csrs.cycle += 1;
+        self.commit = None;
+
+        let result = self.fetch().and_then(|word| {
+            let (instruction, len) =
+                decode_instruction(word).map_err(|_| Trap::IllegalInstruction(word))?;
+            if self.trace {
+                self.commit = Some(Commit::new(self.pc, word, len, instruction));
+            }
+            self.execute(instruction, len)
+        });
+
+        match result {
+            Ok(()) => self.csrs.instret += 1,
+            Err(trap) => {
+                self.commit = None;
+                self.take_trap(trap);
+            }
+        }
+
+        result
+    }
+
+    /// Enters the handler at `mtvec` with interrupts disabled. `mepc` is the
+    /// pc of the instruction that trapped.
+    pub fn take_trap(&mut self, trap: Trap) {
+        let mstatus = self.csrs.mstatus;
+        let mpie = if mstatus & MSTATUS_MIE != 0 {
+            MSTATUS_MPIE
+        } else {
+            0
+        };
+
+        self.csrs.mstatus = (mstatus & !(MSTATUS_MIE | MSTATUS_MPIE)) | mpie | MSTATUS_MPP;
+        self.csrs.mepc = self.pc;
+        self.csrs.mcause = trap.cause();
+        self.csrs.mtval = trap.tval();
+        self.pc = self.csrs.mtvec;
+    }
+
+    fn execute_alu(&mut self, instruction: &Instruction, a: u32, b: u32) -> Option<u32> {
+        let expected = alu(instruction, a, b)?;
+
+        match self.hybrid.as_mut() {
+            Some(hybrid) => {
+                let sample = hybrid.execute(self.pc, instruction, a, b, expected);
+                self.csrs.ntick += sample.ticks as u64;
+                Some(sample.value)
+            }
+            None => Some(expected),
+        }
+    }
+
+    /// `write` is false for set/clear with a zero rs1 or uimm field, which
+    /// only read the CSR. Swaps into x0 skip the read instead.
+    fn execute_csr(
+        &mut self,
+        instruction: &Instruction,
+        rd: u8,
+        csr: u16,
+        operand: u32,
+        write: bool,
+    ) -> Result<(), Trap> {
+        let illegal = Trap::IllegalInstruction(encode_instruction(instruction));
+        let swap = matches!(
+            instruction,
+            Instruction::Csrrw { .. } | Instruction::Csrrwi { .. }
+        );
+        let old = if swap && rd == 0 {
+            0
+        } else {
+            self.csrs.read(csr).ok_or(illegal)?
+        };
+
+        let new = match instruction {
+            _ if swap => operand,
+            Instruction::Csrrs { .. } | Instruction::Csrrsi { .. } => old | operand,
+            _ => old & !operand,
+        };
+        if write {
+            self.csrs.write(csr, new).ok_or(illegal)?;
+            self.log(Write::Csr(csr, self.csrs.read(csr).unwrap_or(new)));
+        }
+
+        self.write_reg(rd, old);
+        Ok(())
+    }
+
+    /// `len` is the encoded size in bytes, 2 for compressed instructions.
+    pub fn execute(&mut self, instruction: Instruction, len: u32) -> Result<(), Trap> {
+        let pc = self.pc;
+        let mut next_pc = pc.wrapping_add(len);
+
+        match instruction {
+            Instruction::Add { rd, rs1, rs2 }
+            | Instruction::Sub { rd, rs1, rs2 }
+            | Instruction::Sll { rd, rs1, rs2 }
+            | Instruction::Slt { rd, rs1, rs2 }
+            | Instruction::Sltu { rd, rs1, rs2 }
+            | Instruction::Xor { rd, rs1, rs2 }
+            | Instruction::Srl { rd, rs1, rs2 }
+            | Instruction::Sra { rd, rs1, rs2 }
+            | Instruction::Or { rd, rs1, rs2 }
+            | Instruction::And { rd, rs1, rs2 } => {
+                let (a, b) = (self.read_reg(rs1), self.read_reg(rs2));
+                if let Some(value) = self.execute_alu(&instruction, a, b) {
+                    self.write_reg(rd, value);
+                }
+            }
+
+            Instruction::Mul { rd, rs1, rs2 }
+            | Instruction::Mulh { rd, rs1, rs2 }
+            | Instruction::Mulhsu { rd, rs1, rs2 }
+            | Instruction::Mulhu { rd, rs1, rs2 }
+            | Instruction::Div { rd, rs1, rs2 }
+            | Instruction::Divu { rd, rs1, rs2 }
+            | Instruction::Rem { rd, rs1, rs2 }
+            | Instruction::Remu { rd, rs1, rs2 } => {
+                let (a, b) = (self.read_reg(rs1), self.read_reg(rs2));
+                if let Some(value) = muldiv(&instruction, a, b) {
+                    self.write_reg(rd, value);
+                }
+            }
+
+            Instruction::Addi { rd, rs1, imm }
+            | Instruction::Slti { rd, rs1, imm }
+            | Instruction::Sltiu { rd, rs1, imm }
+            | Instruction::Xori { rd, rs1, imm }
+            | Instruction::Ori { rd, rs1, imm }
+            | Instruction::Andi { rd, rs1, imm } => {
+                let a = self.read_reg(rs1);
+                if let Some(value) = self.execute_alu(&instruction, a, imm as u32) {
+                    self.write_reg(rd, value);
+                }
+            }
+            Instruction::Slli { rd, rs1, shamt }
+            | Instruction::Srli { rd, rs1, shamt }
+            | Instruction::Srai { rd, rs1, shamt } => {
+                let a = self.read_reg(rs1);
+                if let Some(value) = self.execute_alu(&instruction, a, shamt as u32) {
+                    self.write_reg(rd, value);
+                }
+            }
+
+            Instruction::Lb { rd, rs1, imm } => {
+                let addr = self.read_reg(rs1).wrapping_add(imm as u32);
+                let value = self.load(addr, 1)? as u8 as i8 as i32 as u32;
+                self.write_reg(rd, value);
+            }
+            Instruction::Lh { rd, rs1, imm } => {
+                let addr = self.read_reg(rs1).wrapping_add(imm as u32);
+                let value = self.load(addr, 2)? as u16 as i16 as i32 as u32;
+                self.write_reg(rd, value);
+            }
+            Instruction::Lw { rd, rs1, imm } => {
+                let addr = self.read_reg(rs1).wrapping_add(imm as u32);
+                let value = self.load(addr, 4)?;
+                self.write_reg(rd, value);
+            }
+            Instruction::Lbu { rd, rs1, imm } => {
+                let addr = self.read_reg(rs1).wrapping_add(imm as u32);
+                let value = self.load(addr, 1)?;
+                self.write_reg(rd, value);
+            }
+            Instruction::Lhu { rd, rs1, imm } => {
+                let addr = self.read_reg(rs1).wrapping_add(imm as u32);
+                let value = self.load(addr, 2)?;
+                self.write_reg(rd, value);
+            }
+
+            Instruction::Sb { rs1, rs2, imm } => {
+                let addr = self.read_reg(rs1).wrapping_add(imm as u32);
+                let value = self.read_reg(rs2);
+                self.store(addr, 1, value)?;
+            }
+            Instruction::Sh { rs1, rs2, imm } => {
+                let addr = self.read_reg(rs1).wrapping_add(imm as u32);
+                let value = self.read_reg(rs2);
+                self.store(addr, 2, value)?;
+            }
+            Instruction::Sw { rs1, rs2, imm } => {
+                let addr = self.read_reg(rs1).wrapping_add(imm as u32);
+                let value = self.read_reg(rs2);
+                self.store(addr, 4, value)?;
+            }
+
+            Instruction::Beq { rs1, rs2, imm }
+            | Instruction::Bne { rs1, rs2, imm }
+            | Instruction::Blt { rs1, rs2, imm }
+            | Instruction::Bge { rs1, rs2, imm }
+            | Instruction::Bltu { rs1, rs2, imm }
+            | Instruction::Bgeu { rs1, rs2, imm } => {
+                let a = self.read_reg(rs1);
+                let b = self.read_reg(rs2);
+
+                let taken = match instruction {
+                    Instruction::Beq { .. } => a == b,
+                    Instruction::Bne { .. } => a != b,
+                    Instruction::Blt { .. } => (a as i32) < (b as i32),
+                    Instruction::Bge { .. } => (a as i32) >= (b as i32),
+                    Instruction::Bltu { .. } => a < b,
+                    _ => a >= b,
+                };
+
+                if taken {
+                    next_pc = jump_target(pc.wrapping_add(imm as u32))?;
+                }
+            }
+
+            Instruction::Jal { rd, imm } => {
+                next_pc = jump_target(pc.wrapping_add(imm as u32))?;
+                self.write_reg(rd, pc.wrapping_add(len));
+            }
+            Instruction::Jalr { rd, rs1, imm } => {
+                next_pc = jump_target(self.read_reg(rs1).wrapping_add(imm as u32) & !1)?;
+                self.write_reg(rd, pc.wrapping_add(len));
+            }
+
+            Instruction::Lui { rd, imm } => self.write_reg(rd, imm as u32),
+            Instruction::Auipc { rd, imm } => self.write_reg(rd, pc.wrapping_add(imm as u32)),
+
+            // Single hart with no caches: ordering is already sequential.
+            Instruction::Fence { .. } => {}
+            Instruction::Ecall => return Err(Trap::EnvironmentCall(pc)),
+            Instruction::Ebreak => return Err(Trap::Breakpoint(pc)),
+            Instruction::Mret => {
+                let mstatus = self.csrs.mstatus;
+                let mie = if mstatus & MSTATUS_MPIE != 0 {
+                    MSTATUS_MIE
+                } else {
+                    0
+                };
+
+                self.csrs.mstatus = (mstatus & !MSTATUS_MIE) | mie | MSTATUS_MPIE;
+                self.log(Write::Csr(MSTATUS, self.csrs.mstatus));
+                next_pc = self.csrs.mepc;
+            }
+
+            Instruction::Csrrw { rd, rs1, csr }
+            | Instruction::Csrrs { rd, rs1, csr }
+            | Instruction::Csrrc { rd, rs1, csr } => {
+                let write = rs1 != 0 || matches!(instruction, Instruction::Csrrw { .. });
+                let operand = self.read_reg(rs1);
+                self.execute_csr(&instruction, rd, csr, operand, write)?;
+            }
+            Instruction::Csrrwi { rd, uimm, csr }
+            | Instruction::Csrrsi { rd, uimm, csr }
+            | Instruction::Csrrci { rd, uimm, csr } => {
+                let write = uimm != 0 || matches!(instruction, Instruction::Csrrwi { .. });
+                self.execute_csr(&instruction, rd, csr, uimm as u32, write)?;
+            }
+        }
+
+        self.pc = next_pc;
+
+        Ok(())
+    }
+}
+
+/// With compressed instructions, targets only need 2-byte alignment.
+fn jump_target(target: u32) -> Result<u32, Trap> {
+    if !target.is_multiple_of(2) {
+        return Err(Trap::InstructionAddressMisaligned(target));
+    }
+    Ok(target)
+}
+
+/// Reference result for the register-register and register-immediate ALU ops.
+/// `b` is rs2, the sign-extended immediate or the shift amount. Returns `None`
+/// for instructions that don't go through the ALU.
+pub fn alu(instruction: &Instruction, a: u32, b: u32) -> Option<u32> {
+    let shamt = b & 0x1f;
+
+    let value = match instruction {
+        Instruction::Add { .. } | Instruction::Addi { .. } => a.wrapping_add(b),
+        Instruction::Sub { .. } => a.wrapping_sub(b),
+        Instruction::Sll { .. } | Instruction::Slli { .. } => a << shamt,
+        Instruction::Slt { .. } | Instruction::Slti { .. } => ((a as i32) < (b as i32)) as u32,
+        Instruction::Sltu { .. } | Instruction::Sltiu { .. } => (a < b) as u32,
+        Instruction::Xor { .. } | Instruction::Xori { .. } => a ^ b,
+        Instruction::Srl { .. } | Instruction::Srli { .. } => a >> shamt,
+        Instruction::Sra { .. } | Instruction::Srai { .. } => ((a as i32) >> shamt) as u32,
+        Instruction::Or { .. } | Instruction::Ori { .. } => a | b,
+        Instruction::And { .. } | Instruction::Andi { .. } => a & b,
+        _ => return None,
+    };
+
+    Some(value)
+}
+
+/// RV32M. Division by zero and `i32::MIN / -1` return the results the spec
+/// defines instead of trapping.
+pub fn muldiv(instruction: &Instruction, a: u32, b: u32) -> Option<u32> {
+    let (sa, sb) = (a as i32, b as i32);
+
+    let value = match instruction {
+        Instruction::Mul { .. } => a.wrapping_mul(b),
+        Instruction::Mulh { .. } => ((sa as i64 * sb as i64) >> 32) as u32,
+        Instruction::Mulhsu { .. } => ((sa as i64 * b as i64) >> 32) as u32,
+        Instruction::Mulhu { .. } => ((a as u64 * b as u64) >> 32) as u32,
+        Instruction::Div { .. } => match sb {
+            0 => u32::MAX,
+            _ => sa.wrapping_div(sb) as u32,
+        },
+        Instruction::Divu { .. } => a.checked_div(b).unwrap_or(u32::MAX),
+        Instruction::Rem { .. } => match sb {
+            0 => a,
+            _ => sa.wrapping_rem(sb) as u32,
+        },
+        Instruction::Remu { .. } => a.checked_rem(b).unwrap_or(a),
+        _ => return None,
+    };
+
+    Some(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::csr::{MHARTID, MSCRATCH};
+
+    #[test]
+    fn skips_csr_accesses_the_encoding_rules_out() {
+        let mut cpu = Cpu::new(Memory::new(0, 16));
+        cpu.regs[1] = 7;
+
+        // Reading a read-only CSR is fine as long as nothing is written.
+        let read = Instruction::Csrrs {
+            rd: 2,
+            rs1: 0,
+            csr: MHARTID,
+        };
+        assert_eq!(cpu.execute(read, 4), Ok(()));
+        let write = Instruction::Csrrs {
+            rd: 2,
+            rs1: 1,
+            csr: MHARTID,
+        };
+        assert!(cpu.execute(write, 4).is_err());
+
+        let swap = Instruction::Csrrw {
+            rd: 0,
+            rs1: 1,
+            csr: MSCRATCH,
+        };
+        assert_eq!(cpu.execute(swap, 4), Ok(()));
+        assert_eq!(cpu.csrs.mscratch, 7);
+        assert_eq!(cpu.regs[0], 0);
+    }
+}
@@ -0,0 +1,147 @@
+pub const MSTATUS: u16 = 0x300;
+pub const MISA: u16 = 0x301;
+pub const MIE: u16 = 0x304;
+pub const MTVEC: u16 = 0x305;
+pub const MSCRATCH: u16 = 0x340;
+pub const MEPC: u16 = 0x341;
+pub const MCAUSE: u16 = 0x342;
+pub const MTVAL: u16 = 0x343;
+pub const MIP: u16 = 0x344;
+pub const MCYCLE: u16 = 0xb00;
+pub const MINSTRET: u16 = 0xb02;
+pub const MCYCLEH: u16 = 0xb80;
+pub const MINSTRETH: u16 = 0xb82;
+pub const CYCLE: u16 = 0xc00;
+pub const INSTRET: u16 = 0xc02;
+pub const CYCLEH: u16 = 0xc80;
+pub const INSTRETH: u16 = 0xc82;
+/// Custom read-only counter of ticks spent settling spiking ALU circuits.
+pub const NTICK: u16 = 0xcc0;
+pub const NTICKH: u16 = 0xcc1;
+pub const MHARTID: u16 = 0xf14;
+
+pub const MSTATUS_MIE: u32 = 1 << 3;
+pub const MSTATUS_MPIE: u32 = 1 << 7;
+pub const MSTATUS_MPP: u32 = 0b11 << 11;
+
+/// RV32 with the I, M and C extensions.
+pub const MISA_VALUE: u32 = 1 << 30
+    | 1 << ('C' as u32 - 'A' as u32)
+    | 1 << ('I' as u32 - 'A' as u32)
+    | 1 << ('M' as u32 - 'A' as u32);
+
+pub const NAMES: [(u16, &str); 20] = [
+    (MSTATUS, "mstatus"),
+    (MISA, "misa"),
+    (MIE, "mie"),
+    (MTVEC, "mtvec"),
+    (MSCRATCH, "mscratch"),
+    (MEPC, "mepc"),
+    (MCAUSE, "mcause"),
+    (MTVAL, "mtval"),
+    (MIP, "mip"),
+    (MCYCLE, "mcycle"),
+    (MINSTRET, "minstret"),
+    (MCYCLEH, "mcycleh"),
+    (MINSTRETH, "minstreth"),
+    (CYCLE, "cycle"),
+    (INSTRET, "instret"),
+    (CYCLEH, "cycleh"),
+    (INSTRETH, "instreth"),
+    (NTICK, "ntick"),
+    (NTICKH, "ntickh"),
+    (MHARTID, "mhartid"),
+];
+
+pub fn name(csr: u16) -> Option<&'static str> {
+    NAMES.iter().find(|(addr, _)| *addr == csr).map(|(_, n)| *n)
+}
+
+pub fn parse(name: &str) -> Option<u16> {
+    NAMES
+        .iter()
+        .find(|(_, n)| *n == name)
+        .map(|(addr, _)| *addr)
+}
+
+/// CSR addresses with bits 11:10 set are read-only.
+pub fn is_read_only(csr: u16) -> bool {
+    csr >> 10 == 0b11
+}
+
+/// Machine-mode CSR file of a single hart.
+#[derive(Clone, Debug, Default)]
+pub struct Csrs {
+    pub mstatus: u32,
+    pub mie: u32,
+    pub mtvec: u32,
+    pub mscratch: u32,
+    pub mepc: u32,
+    pub mcause: u32,
+    pub mtval: u32,
+    pub cycle: u64,
+    pub instret: u64,
+    pub ntick: u64,
+}
+
+impl Csrs {
+    pub fn new() -> Self {
+        Self {
+            mstatus: MSTATUS_MPP,
+            ..Self::default()
+        }
+    }
+
+    /// Returns `None` for CSRs that don't exist.
+    pub fn read(&self, csr: u16) -> Option<u32> {
+        Some(match csr {
+            MSTATUS => self.mstatus,
+            MISA => MISA_VALUE,
+            MIE => self.mie,
+            MTVEC => self.mtvec,
+            MSCRATCH => self.mscratch,
+            MEPC => self.mepc,
+            MCAUSE => self.mcause,
+            MTVAL => self.mtval,
+            MIP => 0,
+            MCYCLE | CYCLE => self.cycle as u32,
+            MCYCLEH | CYCLEH => (self.cycle >> 32) as u32,
+            MINSTRET | INSTRET => self.instret as u32,
+            MINSTRETH | INSTRETH => (self.instret >> 32) as u32,
+            NTICK => self.ntick as u32,
+            NTICKH => (self.ntick >> 32) as u32,
+            MHARTID => 0,
+            _ => return None,
+        })
+    }
+
+    /// Returns `None` for CSRs that don't exist or are read-only. WARL fields
+    /// are legalised rather than rejected.
+    pub fn write(&mut self, csr: u16, value: u32) -> Option<()> {
+        if is_read_only(csr) {
+            return None;
+        }
+
+        match csr {
+            // Only machine mode exists, so MPP stays hardwired to M.
+            MSTATUS => {
+                self.mstatus = (value & (MSTATUS_MIE | MSTATUS_MPIE)) | MSTATUS_MPP;
+            }
+            MISA | MIP => {}
+            MIE => self.mie = value,
+            // Direct mode only; the vectored mode bit is dropped.
+            MTVEC => self.mtvec = value & !0b11,
+            MSCRATCH => self.mscratch = value,
+            MEPC => self.mepc = value & !0b1,
+            MCAUSE => self.mcause = value,
+            MTVAL => self.mtval = value,
+            MCYCLE => self.cycle = (self.cycle & !0xffff_ffff) | value as u64,
+            MCYCLEH => self.cycle = (self.cycle & 0xffff_ffff) | (value as u64) << 32,
+            MINSTRET => self.instret = (self.instret & !0xffff_ffff) | value as u64,
+            MINSTRETH => self.instret = (self.instret & 0xffff_ffff) | (value as u64) << 32,
+            _ => return None,
+        }
+
+        Some(())
+    }
+}
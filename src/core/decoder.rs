@@ -1,30 +1,186 @@
-use crate::core::instructions::{DecodedInstruction, Instruction};
+use crate::core::{
+    compressed::{expand, is_compressed},
+    instructions::{DecodedInstruction, Instruction},
+};
 use anyhow::anyhow;
 
+pub const OP_LUI: u8 = 0b0110111;
+pub const OP_AUIPC: u8 = 0b0010111;
+pub const OP_JAL: u8 = 0b1101111;
+pub const OP_JALR: u8 = 0b1100111;
+pub const OP_BRANCH: u8 = 0b1100011;
+pub const OP_LOAD: u8 = 0b0000011;
+pub const OP_STORE: u8 = 0b0100011;
+pub const OP_IMM: u8 = 0b0010011;
+pub const OP: u8 = 0b0110011;
+pub const OP_MISC_MEM: u8 = 0b0001111;
+pub const OP_SYSTEM: u8 = 0b1110011;
+
 pub fn decode(instruction: u32) -> anyhow::Result<DecodedInstruction> {
     let op = (instruction & 0x7f) as u8;
 
+    let rd = ((instruction >> 7) & 0x1f) as u8;
+    let funct3 = ((instruction >> 12) & 0x07) as u8;
+    let rs1 = ((instruction >> 15) & 0x1f) as u8;
+    let rs2 = ((instruction >> 20) & 0x1f) as u8;
+    let funct7 = ((instruction >> 25) & 0x7f) as u8;
+
     match op {
-        0b0110011 => {
-            let rd = ((instruction >> 7) & 0x1f) as u8;
-            let funct3 = ((instruction >> 12) & 0x07) as u8;
-            let rs1 = ((instruction >> 15) & 0x1f) as u8;
-            let rs2 = ((instruction >> 20) & 0x1f) as u8;
-            let funct7 = ((instruction >> 25) & 0x7f) as u8;
-
-            Ok(DecodedInstruction::R {
-                op,
-                rd,
-                funct3,
-                rs1,
-                rs2,
-                funct7,
-            })
+        OP => Ok(DecodedInstruction::R {
+            op,
+            rd,
+            funct3,
+            rs1,
+            rs2,
+            funct7,
+        }),
+        OP_IMM | OP_LOAD | OP_JALR | OP_MISC_MEM | OP_SYSTEM => Ok(DecodedInstruction::I {
+            op,
+            rd,
+            funct3,
+            rs1,
+            imm: imm_i(instruction),
+        }),
+        OP_STORE => Ok(DecodedInstruction::S {
+            op,
+            funct3,
+            rs1,
+            rs2,
+            imm: imm_s(instruction),
+        }),
+        OP_BRANCH => Ok(DecodedInstruction::B {
+            op,
+            funct3,
+            rs1,
+            rs2,
+            imm: imm_b(instruction),
+        }),
+        OP_LUI | OP_AUIPC => Ok(DecodedInstruction::U {
+            op,
+            rd,
+            imm: imm_u(instruction),
+        }),
+        OP_JAL => Ok(DecodedInstruction::J {
+            op,
+            rd,
+            imm: imm_j(instruction),
+        }),
+        _ => Err(anyhow!("Unsupported opcode {op:#09b}")),
+    }
+}
+
+pub fn encode(decoded: DecodedInstruction) -> u32 {
+    match decoded {
+        DecodedInstruction::R {
+            op,
+            rd,
+            funct3,
+            rs1,
+            rs2,
+            funct7,
+        } => {
+            (funct7 as u32) << 25
+                | (rs2 as u32) << 20
+                | (rs1 as u32) << 15
+                | (funct3 as u32) << 12
+                | (rd as u32) << 7
+                | op as u32
+        }
+        DecodedInstruction::I {
+            op,
+            rd,
+            funct3,
+            rs1,
+            imm,
+        } => {
+            (imm as u32) << 20
+                | (rs1 as u32) << 15
+                | (funct3 as u32) << 12
+                | (rd as u32) << 7
+                | op as u32
+        }
+        DecodedInstruction::S {
+            op,
+            funct3,
+            rs1,
+            rs2,
+            imm,
+        } => {
+            let imm = imm as u32;
+            ((imm >> 5) & 0x7f) << 25
+                | (rs2 as u32) << 20
+                | (rs1 as u32) << 15
+                | (funct3 as u32) << 12
+                | (imm & 0x1f) << 7
+                | op as u32
+        }
+        DecodedInstruction::B {
+            op,
+            funct3,
+            rs1,
+            rs2,
+            imm,
+        } => {
+            let imm = imm as u32;
+            ((imm >> 12) & 0x1) << 31
+                | ((imm >> 5) & 0x3f) << 25
+                | (rs2 as u32) << 20
+                | (rs1 as u32) << 15
+                | (funct3 as u32) << 12
+                | ((imm >> 1) & 0xf) << 8
+                | ((imm >> 11) & 0x1) << 7
+                | op as u32
+        }
+        DecodedInstruction::U { op, rd, imm } => {
+            (imm as u32 & 0xffff_f000) | (rd as u32) << 7 | op as u32
+        }
+        DecodedInstruction::J { op, rd, imm } => {
+            let imm = imm as u32;
+            ((imm >> 20) & 0x1) << 31
+                | ((imm >> 1) & 0x3ff) << 21
+                | ((imm >> 11) & 0x1) << 20
+                | ((imm >> 12) & 0xff) << 12
+                | (rd as u32) << 7
+                | op as u32
         }
-        _ => Err(anyhow!("Unsupported instruction")),
     }
 }
 
+/// imm[11:0] = inst[31:20]
+fn imm_i(instruction: u32) -> i32 {
+    (instruction as i32) >> 20
+}
+
+/// imm[11:5] = inst[31:25], imm[4:0] = inst[11:7]
+fn imm_s(instruction: u32) -> i32 {
+    let hi = ((instruction as i32) >> 25) << 5;
+    let lo = ((instruction >> 7) & 0x1f) as i32;
+    hi | lo
+}
+
+/// imm[12|10:5] = inst[31:25], imm[4:1|11] = inst[11:7]
+fn imm_b(instruction: u32) -> i32 {
+    let sign = ((instruction as i32) >> 31) << 12;
+    let bit_11 = ((instruction >> 7) & 0x1) << 11;
+    let bits_10_5 = ((instruction >> 25) & 0x3f) << 5;
+    let bits_4_1 = ((instruction >> 8) & 0xf) << 1;
+    sign | (bit_11 | bits_10_5 | bits_4_1) as i32
+}
+
+/// imm[31:12] = inst[31:12]
+fn imm_u(instruction: u32) -> i32 {
+    (instruction & 0xffff_f000) as i32
+}
+
+/// imm[20|10:1|11|19:12] = inst[31:12]
+fn imm_j(instruction: u32) -> i32 {
+    let sign = ((instruction as i32) >> 31) << 20;
+    let bits_19_12 = instruction & 0x000f_f000;
+    let bit_11 = ((instruction >> 20) & 0x1) << 11;
+    let bits_10_1 = ((instruction >> 21) & 0x3ff) << 1;
+    sign | (bits_19_12 | bit_11 | bits_10_1) as i32
+}
+
 pub fn resolve(decoded: DecodedInstruction) -> anyhow::Result<Instruction> {
     match decoded {
         DecodedInstruction::R {
@@ -45,8 +201,390 @@ pub fn resolve(decoded: DecodedInstruction) -> anyhow::Result<Instruction> {
             (0b101, 0b0100000) => Ok(Instruction::Sra { rd, rs1, rs2 }),
             (0b110, 0b0000000) => Ok(Instruction::Or { rd, rs1, rs2 }),
             (0b111, 0b0000000) => Ok(Instruction::And { rd, rs1, rs2 }),
+            (0b000, 0b0000001) => Ok(Instruction::Mul { rd, rs1, rs2 }),
+            (0b001, 0b0000001) => Ok(Instruction::Mulh { rd, rs1, rs2 }),
+            (0b010, 0b0000001) => Ok(Instruction::Mulhsu { rd, rs1, rs2 }),
+            (0b011, 0b0000001) => Ok(Instruction::Mulhu { rd, rs1, rs2 }),
+            (0b100, 0b0000001) => Ok(Instruction::Div { rd, rs1, rs2 }),
+            (0b101, 0b0000001) => Ok(Instruction::Divu { rd, rs1, rs2 }),
+            (0b110, 0b0000001) => Ok(Instruction::Rem { rd, rs1, rs2 }),
+            (0b111, 0b0000001) => Ok(Instruction::Remu { rd, rs1, rs2 }),
+            _ => Err(anyhow!("Unsupported instruction")),
+        },
+        DecodedInstruction::I {
+            op: OP_IMM,
+            rd,
+            funct3,
+            rs1,
+            imm,
+        } => {
+            let shamt = (imm & 0x1f) as u8;
+            let funct7 = ((imm >> 5) & 0x7f) as u8;
+
+            match (funct3, funct7) {
+                (0b000, _) => Ok(Instruction::Addi { rd, rs1, imm }),
+                (0b010, _) => Ok(Instruction::Slti { rd, rs1, imm }),
+                (0b011, _) => Ok(Instruction::Sltiu { rd, rs1, imm }),
+                (0b100, _) => Ok(Instruction::Xori { rd, rs1, imm }),
+                (0b110, _) => Ok(Instruction::Ori { rd, rs1, imm }),
+                (0b111, _) => Ok(Instruction::Andi { rd, rs1, imm }),
+                (0b001, 0b0000000) => Ok(Instruction::Slli { rd, rs1, shamt }),
+                (0b101, 0b0000000) => Ok(Instruction::Srli { rd, rs1, shamt }),
+                (0b101, 0b0100000) => Ok(Instruction::Srai { rd, rs1, shamt }),
+                _ => Err(anyhow!("Unsupported instruction")),
+            }
+        }
+        DecodedInstruction::I {
+            op: OP_LOAD,
+            rd,
+            funct3,
+            rs1,
+            imm,
+        } => match funct3 {
+            0b000 => Ok(Instruction::Lb { rd, rs1, imm }),
+            0b001 => Ok(Instruction::Lh { rd, rs1, imm }),
+            0b010 => Ok(Instruction::Lw { rd, rs1, imm }),
+            0b100 => Ok(Instruction::Lbu { rd, rs1, imm }),
+            0b101 => Ok(Instruction::Lhu { rd, rs1, imm }),
             _ => Err(anyhow!("Unsupported instruction")),
         },
+        DecodedInstruction::I {
+            op: OP_JALR,
+            rd,
+            funct3: 0b000,
+            rs1,
+            imm,
+        } => Ok(Instruction::Jalr { rd, rs1, imm }),
+        DecodedInstruction::I {
+            op: OP_MISC_MEM,
+            funct3: 0b000,
+            imm,
+            ..
+        } => Ok(Instruction::Fence {
+            pred: ((imm >> 4) & 0xf) as u8,
+            succ: (imm & 0xf) as u8,
+        }),
+        DecodedInstruction::I {
+            op: OP_SYSTEM,
+            rd: 0,
+            funct3: 0b000,
+            rs1: 0,
+            imm,
+        } => match imm {
+            0 => Ok(Instruction::Ecall),
+            1 => Ok(Instruction::Ebreak),
+            0x302 => Ok(Instruction::Mret),
+            _ => Err(anyhow!("Unsupported instruction")),
+        },
+        DecodedInstruction::I {
+            op: OP_SYSTEM,
+            rd,
+            funct3,
+            rs1,
+            imm,
+        } => {
+            let csr = (imm & 0xfff) as u16;
+            let uimm = rs1;
+
+            match funct3 {
+                0b001 => Ok(Instruction::Csrrw { rd, rs1, csr }),
+                0b010 => Ok(Instruction::Csrrs { rd, rs1, csr }),
+                0b011 => Ok(Instruction::Csrrc { rd, rs1, csr }),
+                0b101 => Ok(Instruction::Csrrwi { rd, uimm, csr }),
+                0b110 => Ok(Instruction::Csrrsi { rd, uimm, csr }),
+                0b111 => Ok(Instruction::Csrrci { rd, uimm, csr }),
+                _ => Err(anyhow!("Unsupported instruction")),
+            }
+        }
+        DecodedInstruction::S {
+            funct3,
+            rs1,
+            rs2,
+            imm,
+            ..
+        } => match funct3 {
+            0b000 => Ok(Instruction::Sb { rs1, rs2, imm }),
+            0b001 => Ok(Instruction::Sh { rs1, rs2, imm }),
+            0b010 => Ok(Instruction::Sw { rs1, rs2, imm }),
+            _ => Err(anyhow!("Unsupported instruction")),
+        },
+        DecodedInstruction::B {
+            funct3,
+            rs1,
+            rs2,
+            imm,
+            ..
+        } => match funct3 {
+            0b000 => Ok(Instruction::Beq { rs1, rs2, imm }),
+            0b001 => Ok(Instruction::Bne { rs1, rs2, imm }),
+            0b100 => Ok(Instruction::Blt { rs1, rs2, imm }),
+            0b101 => Ok(Instruction::Bge { rs1, rs2, imm }),
+            0b110 => Ok(Instruction::Bltu { rs1, rs2, imm }),
+            0b111 => Ok(Instruction::Bgeu { rs1, rs2, imm }),
+            _ => Err(anyhow!("Unsupported instruction")),
+        },
+        DecodedInstruction::U {
+            op: OP_LUI,
+            rd,
+            imm,
+        } => Ok(Instruction::Lui { rd, imm }),
+        DecodedInstruction::U {
+            op: OP_AUIPC,
+            rd,
+            imm,
+        } => Ok(Instruction::Auipc { rd, imm }),
+        DecodedInstruction::J { rd, imm, .. } => Ok(Instruction::Jal { rd, imm }),
         _ => Err(anyhow!("Unsupported instruction")),
     }
 }
+
+/// Inverse of `resolve`.
+pub fn lower(instruction: &Instruction) -> DecodedInstruction {
+    let r = |funct3: u8, funct7: u8, rd: u8, rs1: u8, rs2: u8| DecodedInstruction::R {
+        op: OP,
+        rd,
+        funct3,
+        rs1,
+        rs2,
+        funct7,
+    };
+    let i = |op: u8, funct3: u8, rd: u8, rs1: u8, imm: i32| DecodedInstruction::I {
+        op,
+        rd,
+        funct3,
+        rs1,
+        imm,
+    };
+    let shift = |funct3: u8, funct7: u8, rd: u8, rs1: u8, shamt: u8| {
+        i(OP_IMM, funct3, rd, rs1, (funct7 as i32) << 5 | shamt as i32)
+    };
+    let s = |funct3: u8, rs1: u8, rs2: u8, imm: i32| DecodedInstruction::S {
+        op: OP_STORE,
+        funct3,
+        rs1,
+        rs2,
+        imm,
+    };
+    let b = |funct3: u8, rs1: u8, rs2: u8, imm: i32| DecodedInstruction::B {
+        op: OP_BRANCH,
+        funct3,
+        rs1,
+        rs2,
+        imm,
+    };
+
+    match *instruction {
+        Instruction::Add { rd, rs1, rs2 } => r(0b000, 0b0000000, rd, rs1, rs2),
+        Instruction::Sub { rd, rs1, rs2 } => r(0b000, 0b0100000, rd, rs1, rs2),
+        Instruction::Sll { rd, rs1, rs2 } => r(0b001, 0b0000000, rd, rs1, rs2),
+        Instruction::Slt { rd, rs1, rs2 } => r(0b010, 0b0000000, rd, rs1, rs2),
+        Instruction::Sltu { rd, rs1, rs2 } => r(0b011, 0b0000000, rd, rs1, rs2),
+        Instruction::Xor { rd, rs1, rs2 } => r(0b100, 0b0000000, rd, rs1, rs2),
+        Instruction::Srl { rd, rs1, rs2 } => r(0b101, 0b0000000, rd, rs1, rs2),
+        Instruction::Sra { rd, rs1, rs2 } => r(0b101, 0b0100000, rd, rs1, rs2),
+        Instruction::Or { rd, rs1, rs2 } => r(0b110, 0b0000000, rd, rs1, rs2),
+        Instruction::And { rd, rs1, rs2 } => r(0b111, 0b0000000, rd, rs1, rs2),
+        Instruction::Mul { rd, rs1, rs2 } => r(0b000, 0b0000001, rd, rs1, rs2),
+        Instruction::Mulh { rd, rs1, rs2 } => r(0b001, 0b0000001, rd, rs1, rs2),
+        Instruction::Mulhsu { rd, rs1, rs2 } => r(0b010, 0b0000001, rd, rs1, rs2),
+        Instruction::Mulhu { rd, rs1, rs2 } => r(0b011, 0b0000001, rd, rs1, rs2),
+        Instruction::Div { rd, rs1, rs2 } => r(0b100, 0b0000001, rd, rs1, rs2),
+        Instruction::Divu { rd, rs1, rs2 } => r(0b101, 0b0000001, rd, rs1, rs2),
+        Instruction::Rem { rd, rs1, rs2 } => r(0b110, 0b0000001, rd, rs1, rs2),
+        Instruction::Remu { rd, rs1, rs2 } => r(0b111, 0b0000001, rd, rs1, rs2),
+
+        Instruction::Addi { rd, rs1, imm } => i(OP_IMM, 0b000, rd, rs1, imm),
+        Instruction::Slti { rd, rs1, imm } => i(OP_IMM, 0b010, rd, rs1, imm),
+        Instruction::Sltiu { rd, rs1, imm } => i(OP_IMM, 0b011, rd, rs1, imm),
+        Instruction::Xori { rd, rs1, imm } => i(OP_IMM, 0b100, rd, rs1, imm),
+        Instruction::Ori { rd, rs1, imm } => i(OP_IMM, 0b110, rd, rs1, imm),
+        Instruction::Andi { rd, rs1, imm } => i(OP_IMM, 0b111, rd, rs1, imm),
+        Instruction::Slli { rd, rs1, shamt } => shift(0b001, 0b0000000, rd, rs1, shamt),
+        Instruction::Srli { rd, rs1, shamt } => shift(0b101, 0b0000000, rd, rs1, shamt),
+        Instruction::Srai { rd, rs1, shamt } => shift(0b101, 0b0100000, rd, rs1, shamt),
+
+        Instruction::Lb { rd, rs1, imm } => i(OP_LOAD, 0b000, rd, rs1, imm),
+        Instruction::Lh { rd, rs1, imm } => i(OP_LOAD, 0b001, rd, rs1, imm),
+        Instruction::Lw { rd, rs1, imm } => i(OP_LOAD, 0b010, rd, rs1, imm),
+        Instruction::Lbu { rd, rs1, imm } => i(OP_LOAD, 0b100, rd, rs1, imm),
+        Instruction::Lhu { rd, rs1, imm } => i(OP_LOAD, 0b101, rd, rs1, imm),
+
+        Instruction::Sb { rs1, rs2, imm } => s(0b000, rs1, rs2, imm),
+        Instruction::Sh { rs1, rs2, imm } => s(0b001, rs1, rs2, imm),
+        Instruction::Sw { rs1, rs2, imm } => s(0b010, rs1, rs2, imm),
+
+        Instruction::Beq { rs1, rs2, imm } => b(0b000, rs1, rs2, imm),
+        Instruction::Bne { rs1, rs2, imm } => b(0b001, rs1, rs2, imm),
+        Instruction::Blt { rs1, rs2, imm } => b(0b100, rs1, rs2, imm),
+        Instruction::Bge { rs1, rs2, imm } => b(0b101, rs1, rs2, imm),
+        Instruction::Bltu { rs1, rs2, imm } => b(0b110, rs1, rs2, imm),
+        Instruction::Bgeu { rs1, rs2, imm } => b(0b111, rs1, rs2, imm),
+
+        Instruction::Jal { rd, imm } => DecodedInstruction::J {
+            op: OP_JAL,
+            rd,
+            imm,
+        },
+        Instruction::Jalr { rd, rs1, imm } => i(OP_JALR, 0b000, rd, rs1, imm),
+
+        Instruction::Lui { rd, imm } => DecodedInstruction::U {
+            op: OP_LUI,
+            rd,
+            imm,
+        },
+        Instruction::Auipc { rd, imm } => DecodedInstruction::U {
+            op: OP_AUIPC,
+            rd,
+            imm,
+        },
+
+        Instruction::Fence { pred, succ } => i(
+            OP_MISC_MEM,
+            0b000,
+            0,
+            0,
+            ((pred & 0xf) << 4 | (succ & 0xf)) as i32,
+        ),
+        Instruction::Ecall => i(OP_SYSTEM, 0b000, 0, 0, 0),
+        Instruction::Ebreak => i(OP_SYSTEM, 0b000, 0, 0, 1),
+        Instruction::Mret => i(OP_SYSTEM, 0b000, 0, 0, 0x302),
+
+        Instruction::Csrrw { rd, rs1, csr } => i(OP_SYSTEM, 0b001, rd, rs1, csr_imm(csr)),
+        Instruction::Csrrs { rd, rs1, csr } => i(OP_SYSTEM, 0b010, rd, rs1, csr_imm(csr)),
+        Instruction::Csrrc { rd, rs1, csr } => i(OP_SYSTEM, 0b011, rd, rs1, csr_imm(csr)),
+        Instruction::Csrrwi { rd, uimm, csr } => i(OP_SYSTEM, 0b101, rd, uimm, csr_imm(csr)),
+        Instruction::Csrrsi { rd, uimm, csr } => i(OP_SYSTEM, 0b110, rd, uimm, csr_imm(csr)),
+        Instruction::Csrrci { rd, uimm, csr } => i(OP_SYSTEM, 0b111, rd, uimm, csr_imm(csr)),
+    }
+}
+
+/// Decodes a fetched instruction, expanding it first when it is compressed.
+/// Also returns its length in bytes.
+pub fn decode_instruction(word: u32) -> anyhow::Result<(Instruction, u32)> {
+    if is_compressed(word) {
+        Ok((expand(word as u16)?, 2))
+    } else {
+        Ok((resolve(decode(word)?)?, 4))
+    }
+}
+
+/// CSR addresses live in the I-type immediate, which `decode` sign-extends.
+fn csr_imm(csr: u16) -> i32 {
+    ((csr as i32) << 20) >> 20
+}
+
+pub fn encode_instruction(instruction: &Instruction) -> u32 {
+    encode(lower(instruction))
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{Rng, SeedableRng, rngs::StdRng};
+
+    use super::*;
+
+    const OPCODES: [u8; 11] = [
+        OP_LUI,
+        OP_AUIPC,
+        OP_JAL,
+        OP_JALR,
+        OP_BRANCH,
+        OP_LOAD,
+        OP_STORE,
+        OP_IMM,
+        OP,
+        OP_MISC_MEM,
+        OP_SYSTEM,
+    ];
+
+    fn imm(word: u32) -> i32 {
+        match decode(word).unwrap() {
+            DecodedInstruction::R { .. } => panic!("{word:#010x} has no immediate"),
+            DecodedInstruction::I { imm, .. }
+            | DecodedInstruction::S { imm, .. }
+            | DecodedInstruction::B { imm, .. }
+            | DecodedInstruction::U { imm, .. }
+            | DecodedInstruction::J { imm, .. } => imm,
+        }
+    }
+
+    /// Words from `llvm-mc -triple=riscv32 -show-encoding`.
+    #[test]
+    fn sign_extends_immediates() {
+        let cases = [
+            (0xfff0_0093, -1),         // addi ra, zero, -1
+            (0x7ff0_0093, 2047),       // addi ra, zero, 2047
+            (0x8000_0093, -2048),      // addi ra, zero, -2048
+            (0xfe20_ae23, -4),         // sw sp, -4(ra)
+            (0x7e20_afa3, 2047),       // sw sp, 2047(ra)
+            (0x8020_a023, -2048),      // sw sp, -2048(ra)
+            (0x8020_8063, -4096),      // beq ra, sp, -4096: imm[12] only
+            (0x7e20_8fe3, 4094),       // beq ra, sp, 4094
+            (0x0020_80e3, 2048),       // beq ra, sp, 2048: imm[11] only
+            (0x8020_80e3, -2048),      // beq ra, sp, -2048: imm[12] and imm[11]
+            (0x0020_8163, 2),          // beq ra, sp, 2
+            (0xffff_f2b7, -0x1000),    // lui t0, 0xfffff
+            (0x8000_02b7, i32::MIN),   // lui t0, 0x80000
+            (0x0000_1297, 0x1000),     // auipc t0, 1
+            (0x8000_00ef, -0x10_0000), // jal ra, -1048576: imm[20] only
+            (0x7fff_f0ef, 0x0f_fffe),  // jal ra, 1048574
+            (0x0010_00ef, 2048),       // jal ra, 2048: imm[11] only
+            (0x801f_f0ef, -2048),      // jal ra, -2048: imm[20] and imm[11]
+            (0x0020_00ef, 2),          // jal ra, 2
+            (0x0000_10ef, 4096),       // jal ra, 4096: imm[12] only
+            (0x800f_f0ef, -4096),      // jal ra, -4096
+        ];
+
+        for (word, expected) in cases {
+            assert_eq!(imm(word), expected, "{word:#010x}");
+            assert_eq!(encode(decode(word).unwrap()), word, "{word:#010x}");
+        }
+    }
+
+    #[test]
+    fn encode_inverts_decode() {
+        let mut rng = StdRng::seed_from_u64(1);
+        for &op in &OPCODES {
+            for _ in 0..20_000 {
+                let word = rng.random::<u32>() & !0x7f | op as u32;
+                assert_eq!(encode(decode(word).unwrap()), word, "{word:#010x}");
+            }
+            for high in [
+                0,
+                0x8000_0000,
+                0x7fff_ff80,
+                0xffff_ff80,
+                0x8000_0f80,
+                0x0010_0f80,
+            ] {
+                let word = high | op as u32;
+                assert_eq!(encode(decode(word).unwrap()), word, "{word:#010x}");
+            }
+        }
+    }
+
+    #[test]
+    fn lower_inverts_resolve() {
+        let mut rng = StdRng::seed_from_u64(2);
+        let mut resolved = 0;
+        for _ in 0..200_000 {
+            let word = rng.random::<u32>() | 0b11;
+            let Ok(instruction) = decode(word).and_then(resolve) else {
+                continue;
+            };
+            resolved += 1;
+            assert_eq!(
+                resolve(lower(&instruction)).unwrap(),
+                instruction,
+                "{word:#010x}"
+            );
+        }
+        assert!(resolved > 1000);
+    }
+
+    #[test]
+    fn rejects_unknown_opcodes() {
+        assert!(decode(0x0000_0000).is_err());
+        assert!(decode(0xffff_ffff).is_err());
+    }
+}
@@ -0,0 +1,258 @@
+use std::fmt;
+
+use crate::core::{
+    csr,
+    decoder::{decode_instruction, encode_instruction},
+    instructions::{DecodedInstruction, Instruction, reg_name},
+};
+
+/// Renders `word` in the syntax `asm::assemble` accepts. Words that don't
+/// decode, or that decode but don't re-encode to themselves (such as a
+/// `fence` with nonzero `fm`, `rd` or `rs1`), come out as `.word`, so the
+/// output always reassembles to `word`. Compressed instructions are shown
+/// expanded, so they reassemble to the equivalent 32-bit encoding instead.
+pub fn disassemble(word: u32) -> String {
+    match decode_instruction(word) {
+        Ok((instruction, 2)) => instruction.to_string(),
+        Ok((instruction, _)) if encode_instruction(&instruction) == word => instruction.to_string(),
+        _ => format!(".word {word:#010x}"),
+    }
+}
+
+fn fence_set(set: u8) -> String {
+    let set: String = [(0b1000, 'i'), (0b0100, 'o'), (0b0010, 'r'), (0b0001, 'w')]
+        .iter()
+        .filter(|(bit, _)| set & bit != 0)
+        .map(|&(_, c)| c)
+        .collect();
+
+    if set.is_empty() { "0".to_string() } else { set }
+}
+
+fn csr_name(addr: u16) -> String {
+    csr::name(addr).map_or_else(|| format!("{addr:#x}"), str::to_string)
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let m = self.mnemonic();
+
+        match *self {
+            Instruction::Add { rd, rs1, rs2 }
+            | Instruction::Sub { rd, rs1, rs2 }
+            | Instruction::Sll { rd, rs1, rs2 }
+            | Instruction::Slt { rd, rs1, rs2 }
+            | Instruction::Sltu { rd, rs1, rs2 }
+            | Instruction::Xor { rd, rs1, rs2 }
+            | Instruction::Srl { rd, rs1, rs2 }
+            | Instruction::Sra { rd, rs1, rs2 }
+            | Instruction::Or { rd, rs1, rs2 }
+            | Instruction::And { rd, rs1, rs2 }
+            | Instruction::Mul { rd, rs1, rs2 }
+            | Instruction::Mulh { rd, rs1, rs2 }
+            | Instruction::Mulhsu { rd, rs1, rs2 }
+            | Instruction::Mulhu { rd, rs1, rs2 }
+            | Instruction::Div { rd, rs1, rs2 }
+            | Instruction::Divu { rd, rs1, rs2 }
+            | Instruction::Rem { rd, rs1, rs2 }
+            | Instruction::Remu { rd, rs1, rs2 } => write!(
+                f,
+                "{m} {}, {}, {}",
+                reg_name(rd),
+                reg_name(rs1),
+                reg_name(rs2)
+            ),
+
+            Instruction::Addi { rd, rs1, imm }
+            | Instruction::Slti { rd, rs1, imm }
+            | Instruction::Sltiu { rd, rs1, imm }
+            | Instruction::Xori { rd, rs1, imm }
+            | Instruction::Ori { rd, rs1, imm }
+            | Instruction::Andi { rd, rs1, imm } => {
+                write!(f, "{m} {}, {}, {imm}", reg_name(rd), reg_name(rs1))
+            }
+            Instruction::Slli { rd, rs1, shamt }
+            | Instruction::Srli { rd, rs1, shamt }
+            | Instruction::Srai { rd, rs1, shamt } => {
+                write!(f, "{m} {}, {}, {shamt}", reg_name(rd), reg_name(rs1))
+            }
+
+            Instruction::Lb { rd, rs1, imm }
+            | Instruction::Lh { rd, rs1, imm }
+            | Instruction::Lw { rd, rs1, imm }
+            | Instruction::Lbu { rd, rs1, imm }
+            | Instruction::Lhu { rd, rs1, imm }
+            | Instruction::Jalr { rd, rs1, imm } => {
+                write!(f, "{m} {}, {imm}({})", reg_name(rd), reg_name(rs1))
+            }
+
+            Instruction::Sb { rs1, rs2, imm }
+            | Instruction::Sh { rs1, rs2, imm }
+            | Instruction::Sw { rs1, rs2, imm } => {
+                write!(f, "{m} {}, {imm}({})", reg_name(rs2), reg_name(rs1))
+            }
+
+            Instruction::Beq { rs1, rs2, imm }
+            | Instruction::Bne { rs1, rs2, imm }
+            | Instruction::Blt { rs1, rs2, imm }
+            | Instruction::Bge { rs1, rs2, imm }
+            | Instruction::Bltu { rs1, rs2, imm }
+            | Instruction::Bgeu { rs1, rs2, imm } => {
+                write!(f, "{m} {}, {}, {imm}", reg_name(rs1), reg_name(rs2))
+            }
+
+            Instruction::Jal { rd, imm } => write!(f, "{m} {}, {imm}", reg_name(rd)),
+
+            Instruction::Lui { rd, imm } | Instruction::Auipc { rd, imm } => {
+                write!(f, "{m} {}, {:#x}", reg_name(rd), (imm as u32) >> 12)
+            }
+
+            Instruction::Fence { pred, succ } => {
+                write!(f, "{m} {}, {}", fence_set(pred), fence_set(succ))
+            }
+            Instruction::Ecall | Instruction::Ebreak | Instruction::Mret => write!(f, "{m}"),
+
+            Instruction::Csrrw { rd, rs1, csr }
+            | Instruction::Csrrs { rd, rs1, csr }
+            | Instruction::Csrrc { rd, rs1, csr } => {
+                write!(
+                    f,
+                    "{m} {}, {}, {}",
+                    reg_name(rd),
+                    csr_name(csr),
+                    reg_name(rs1)
+                )
+            }
+            Instruction::Csrrwi { rd, uimm, csr }
+            | Instruction::Csrrsi { rd, uimm, csr }
+            | Instruction::Csrrci { rd, uimm, csr } => {
+                write!(f, "{m} {}, {}, {uimm}", reg_name(rd), csr_name(csr))
+            }
+        }
+    }
+}
+
+impl fmt::Display for DecodedInstruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            DecodedInstruction::R {
+                op,
+                rd,
+                funct3,
+                rs1,
+                rs2,
+                funct7,
+            } => write!(
+                f,
+                "R op={op:07b} rd={} funct3={funct3:03b} rs1={} rs2={} funct7={funct7:07b}",
+                reg_name(rd),
+                reg_name(rs1),
+                reg_name(rs2)
+            ),
+            DecodedInstruction::I {
+                op,
+                rd,
+                funct3,
+                rs1,
+                imm,
+            } => write!(
+                f,
+                "I op={op:07b} rd={} funct3={funct3:03b} rs1={} imm={imm}",
+                reg_name(rd),
+                reg_name(rs1)
+            ),
+            DecodedInstruction::S {
+                op,
+                funct3,
+                rs1,
+                rs2,
+                imm,
+            } => write!(
+                f,
+                "S op={op:07b} funct3={funct3:03b} rs1={} rs2={} imm={imm}",
+                reg_name(rs1),
+                reg_name(rs2)
+            ),
+            DecodedInstruction::B {
+                op,
+                funct3,
+                rs1,
+                rs2,
+                imm,
+            } => write!(
+                f,
+                "B op={op:07b} funct3={funct3:03b} rs1={} rs2={} imm={imm}",
+                reg_name(rs1),
+                reg_name(rs2)
+            ),
+            DecodedInstruction::U { op, rd, imm } => {
+                write!(f, "U op={op:07b} rd={} imm={imm:#x}", reg_name(rd))
+            }
+            DecodedInstruction::J { op, rd, imm } => {
+                write!(f, "J op={op:07b} rd={} imm={imm}", reg_name(rd))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{Rng, SeedableRng, rngs::StdRng};
+
+    use super::*;
+    use crate::core::{asm::assemble, compressed::expand};
+
+    fn reassemble(text: &str) -> u32 {
+        let program = assemble(text, 0x8000_0000).unwrap_or_else(|e| panic!("`{text}`: {e}"));
+        assert_eq!(program.words.len(), 1, "`{text}`");
+        program.words[0]
+    }
+
+    #[test]
+    fn reassembles_to_the_same_word() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let mut decoded = 0;
+        for _ in 0..200_000 {
+            let word = rng.random::<u32>() | 0b11;
+            let text = disassemble(word);
+            if !text.starts_with(".word") {
+                decoded += 1;
+            }
+            assert_eq!(reassemble(&text), word, "`{text}`");
+        }
+        assert!(decoded > 10_000);
+
+        for word in [
+            0x0000_0013, // nop
+            0xfff0_0093, // addi ra, zero, -1
+            0x8020_80e3, // beq ra, sp, -2048
+            0x801f_f0ef, // jal ra, -2048
+            0xffff_f2b7, // lui t0, 0xfffff
+            0x0ff0_000f, // fence iorw, iorw
+            0x0000_0073, // ecall
+            0x3020_0073, // mret
+            0x3400_9073, // csrw mscratch, ra
+            0x8330_000f, // fence.tso
+        ] {
+            assert_eq!(reassemble(&disassemble(word)), word, "{word:#010x}");
+        }
+    }
+
+    #[test]
+    fn reassembles_compressed_to_the_expansion() {
+        for half in 0..=u16::MAX {
+            if half & 0b11 == 0b11 {
+                continue;
+            }
+            let Ok(instruction) = expand(half) else {
+                continue;
+            };
+            let text = disassemble(half as u32);
+            assert_eq!(
+                reassemble(&text),
+                encode_instruction(&instruction),
+                "{half:#06x}: `{text}`"
+            );
+        }
+    }
+}
@@ -0,0 +1,228 @@
+use std::collections::HashMap;
+
+use anyhow::{anyhow, bail, ensure};
+
+use crate::core::cpu::Memory;
+
+const ELFCLASS32: u8 = 1;
+const ELFCLASS64: u8 = 2;
+const ELFDATA2LSB: u8 = 1;
+const ET_EXEC: u16 = 2;
+const EM_RISCV: u16 = 243;
+const PT_LOAD: u32 = 1;
+const SHT_SYMTAB: u32 = 2;
+
+#[derive(Clone, Debug)]
+pub struct Segment {
+    pub vaddr: u32,
+    pub data: Vec<u8>,
+    pub mem_size: u32,
+}
+
+#[derive(Clone, Debug)]
+pub struct ElfImage {
+    pub entry: u32,
+    pub segments: Vec<Segment>,
+    pub symbols: HashMap<String, u32>,
+}
+
+impl ElfImage {
+    pub fn symbol(&self, name: &str) -> Option<u32> {
+        self.symbols.get(name).copied()
+    }
+
+    /// The ELF entry point, or the `_start` symbol when `e_entry` is zero.
+    pub fn start(&self) -> u32 {
+        match self.entry {
+            0 => self.symbol("_start").unwrap_or(0),
+            entry => entry,
+        }
+    }
+
+    pub fn tohost(&self) -> Option<u32> {
+        self.symbol("tohost")
+    }
+
+    /// Copies every segment into `memory` and zero-fills the rest of its
+    /// `mem_size`. Segments are checked against the memory bounds before
+    /// anything is written.
+    pub fn load(&self, memory: &mut Memory) -> anyhow::Result<()> {
+        for segment in &self.segments {
+            ensure!(
+                segment.data.len() <= segment.mem_size as usize,
+                "segment at {:#010x} has p_filesz > p_memsz",
+                segment.vaddr
+            );
+
+            let start = segment.vaddr as u64;
+            let end = start + segment.mem_size as u64;
+            let base = memory.base as u64;
+            let limit = base + memory.data.len() as u64;
+            ensure!(
+                base <= start && end <= limit,
+                "segment {start:#010x}..{end:#010x} does not fit in memory {base:#010x}..{limit:#010x}"
+            );
+
+            let offset = (start - base) as usize;
+            let (file, zeros) =
+                memory.data[offset..(end - base) as usize].split_at_mut(segment.data.len());
+            file.copy_from_slice(&segment.data);
+            zeros.fill(0);
+        }
+
+        Ok(())
+    }
+}
+
+pub fn is_elf(bytes: &[u8]) -> bool {
+    bytes.starts_with(b"\x7fELF")
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl Reader<'_> {
+    fn slice(&self, offset: usize, len: usize) -> anyhow::Result<&[u8]> {
+        offset
+            .checked_add(len)
+            .and_then(|end| self.bytes.get(offset..end))
+            .ok_or_else(|| anyhow!("truncated ELF: {len} bytes at offset {offset:#x}"))
+    }
+
+    fn u8(&self, offset: usize) -> anyhow::Result<u8> {
+        Ok(self.slice(offset, 1)?[0])
+    }
+
+    fn u16(&self, offset: usize) -> anyhow::Result<u16> {
+        let bytes = self.slice(offset, 2)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn u32(&self, offset: usize) -> anyhow::Result<u32> {
+        let bytes = self.slice(offset, 4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn cstr(&self, offset: usize) -> anyhow::Result<&[u8]> {
+        let tail = self
+            .bytes
+            .get(offset..)
+            .ok_or_else(|| anyhow!("string offset {offset:#x} out of bounds"))?;
+        let len = tail
+            .iter()
+            .position(|&b| b == 0)
+            .ok_or_else(|| anyhow!("unterminated string at {offset:#x}"))?;
+        Ok(&tail[..len])
+    }
+}
+
+pub fn parse(bytes: &[u8]) -> anyhow::Result<ElfImage> {
+    let r = Reader { bytes };
+
+    ensure!(is_elf(bytes), "not an ELF file");
+
+    match r.u8(4)? {
+        ELFCLASS32 => {}
+        ELFCLASS64 => bail!("64-bit ELF files are not supported, expected ELF32 for RV32"),
+        class => bail!("invalid ELF class {class}"),
+    }
+    ensure!(
+        r.u8(5)? == ELFDATA2LSB,
+        "big-endian ELF files are not supported"
+    );
+
+    let e_type = r.u16(16)?;
+    let e_machine = r.u16(18)?;
+    ensure!(
+        e_machine == EM_RISCV,
+        "not a RISC-V executable (e_machine = {e_machine})"
+    );
+    ensure!(
+        e_type == ET_EXEC,
+        "not a statically linked executable (e_type = {e_type})"
+    );
+
+    let entry = r.u32(24)?;
+    let phoff = r.u32(28)? as usize;
+    let shoff = r.u32(32)? as usize;
+    let phentsize = r.u16(42)? as usize;
+    let phnum = r.u16(44)? as usize;
+    let shentsize = r.u16(46)? as usize;
+    let shnum = r.u16(48)? as usize;
+
+    let mut segments = Vec::new();
+    for i in 0..phnum {
+        let ph = phoff + i * phentsize;
+        if r.u32(ph)? != PT_LOAD {
+            continue;
+        }
+
+        let offset = r.u32(ph + 4)? as usize;
+        let vaddr = r.u32(ph + 8)?;
+        let file_size = r.u32(ph + 16)? as usize;
+        let mem_size = r.u32(ph + 20)?;
+        ensure!(
+            file_size <= mem_size as usize,
+            "segment at {vaddr:#010x} has p_filesz > p_memsz"
+        );
+
+        segments.push(Segment {
+            vaddr,
+            data: r.slice(offset, file_size)?.to_vec(),
+            mem_size,
+        });
+    }
+
+    let mut symbols = HashMap::new();
+    for i in 0..shnum {
+        let sh = shoff + i * shentsize;
+        if r.u32(sh + 4)? != SHT_SYMTAB {
+            continue;
+        }
+
+        let offset = r.u32(sh + 16)? as usize;
+        let size = r.u32(sh + 20)? as usize;
+        let link = r.u32(sh + 24)? as usize;
+        let entsize = (r.u32(sh + 36)? as usize).max(16);
+        let strtab = r.u32(shoff + link * shentsize + 16)? as usize;
+
+        for sym in (offset..offset + size).step_by(entsize) {
+            let name = r.u32(sym)? as usize;
+            if name == 0 {
+                continue;
+            }
+            // Symbols the program cannot name are of no use to us; skip
+            // them rather than rejecting the whole file.
+            let Ok(name) = std::str::from_utf8(r.cstr(strtab + name)?) else {
+                continue;
+            };
+            symbols.insert(name.to_string(), r.u32(sym + 4)?);
+        }
+    }
+
+    Ok(ElfImage {
+        entry,
+        segments,
+        symbols,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn image(entry: u32) -> ElfImage {
+        ElfImage {
+            entry,
+            segments: Vec::new(),
+            symbols: HashMap::from([("_start".to_string(), 0x8000_0100)]),
+        }
+    }
+
+    #[test]
+    fn starts_at_the_entry_point() {
+        assert_eq!(image(0x8000_0000).start(), 0x8000_0000);
+        assert_eq!(image(0).start(), 0x8000_0100);
+    }
+}
@@ -0,0 +1,124 @@
+use std::collections::BTreeMap;
+
+use crate::core::{instructions::Instruction, spiking_alu::SpikingAlu};
+
+/// One ALU operation that ran on a spiking circuit.
+#[derive(Clone, Copy, Debug)]
+pub struct AluSample {
+    pub pc: u32,
+    pub instruction: Instruction,
+    pub a: u32,
+    pub b: u32,
+    pub value: u32,
+    pub expected: u32,
+    pub ticks: u32,
+    pub spikes: usize,
+    pub neurons: usize,
+}
+
+impl AluSample {
+    pub fn mismatch(&self) -> bool {
+        self.value != self.expected
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct OpSummary {
+    pub count: usize,
+    pub ticks: u64,
+    pub spikes: u64,
+    pub neurons: usize,
+    pub mismatches: usize,
+}
+
+/// Runs ALU ops on spiking circuits and checks each result against the
+/// reference ALU.
+///
+/// Only per-op totals and the first `MAX_MISMATCH_SAMPLES` mismatching
+/// samples are kept, so memory stays flat however long the program runs.
+#[derive(Debug)]
+pub struct HybridAlu {
+    alu: SpikingAlu,
+    ops: usize,
+    summary: BTreeMap<&'static str, OpSummary>,
+    mismatches: Vec<AluSample>,
+    mismatch_count: usize,
+}
+
+/// Most mismatching samples `HybridAlu` keeps; later ones are only counted.
+pub const MAX_MISMATCH_SAMPLES: usize = 64;
+
+impl HybridAlu {
+    pub fn new() -> anyhow::Result<Self> {
+        Ok(Self {
+            alu: SpikingAlu::new()?,
+            ops: 0,
+            summary: BTreeMap::new(),
+            mismatches: Vec::new(),
+            mismatch_count: 0,
+        })
+    }
+
+    /// Returns the recorded sample. Its `value` is the spiking result, which is
+    /// what gets written back even when it disagrees with `expected`.
+    pub fn execute(
+        &mut self,
+        pc: u32,
+        instruction: &Instruction,
+        a: u32,
+        b: u32,
+        expected: u32,
+    ) -> AluSample {
+        let run = self
+            .alu
+            .execute(instruction, a, b)
+            .expect("reference ALU accepted the instruction");
+
+        let sample = AluSample {
+            pc,
+            instruction: *instruction,
+            a,
+            b,
+            value: run.value,
+            expected,
+            ticks: run.ticks,
+            spikes: run.spikes,
+            neurons: run.neurons,
+        };
+
+        self.ops += 1;
+        let entry = self.summary.entry(instruction.mnemonic()).or_default();
+        entry.count += 1;
+        entry.ticks += sample.ticks as u64;
+        entry.spikes += sample.spikes as u64;
+        entry.neurons = sample.neurons;
+        if sample.mismatch() {
+            entry.mismatches += 1;
+            self.mismatch_count += 1;
+            if self.mismatches.len() < MAX_MISMATCH_SAMPLES {
+                self.mismatches.push(sample);
+            }
+        }
+
+        sample
+    }
+
+    /// ALU ops run so far.
+    pub fn ops(&self) -> usize {
+        self.ops
+    }
+
+    /// The first `MAX_MISMATCH_SAMPLES` mismatching samples.
+    pub fn mismatches(&self) -> &[AluSample] {
+        &self.mismatches
+    }
+
+    /// Mismatches seen so far, including those past the kept samples.
+    pub fn mismatch_count(&self) -> usize {
+        self.mismatch_count
+    }
+
+    pub fn summary(&self) -> &BTreeMap<&'static str, OpSummary> {
+        &self.summary
+    }
+}
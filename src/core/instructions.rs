@@ -1,3 +1,14 @@
+pub const ABI_NAMES: [&str; 32] = [
+    "zero", "ra", "sp", "gp", "tp", "t0", "t1", "t2", "s0", "s1", "a0", "a1", "a2", "a3", "a4",
+    "a5", "a6", "a7", "s2", "s3", "s4", "s5", "s6", "s7", "s8", "s9", "s10", "s11", "t3", "t4",
+    "t5", "t6",
+];
+
+pub fn reg_name(reg: u8) -> &'static str {
+    ABI_NAMES[reg as usize & 0x1f]
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum DecodedInstruction {
     R {
         op: u8,
@@ -40,6 +51,7 @@ pub enum DecodedInstruction {
     },
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Instruction {
     Add { rd: u8, rs1: u8, rs2: u8 },
     Sub { rd: u8, rs1: u8, rs2: u8 },
@@ -51,4 +63,120 @@ pub enum Instruction {
     Sra { rd: u8, rs1: u8, rs2: u8 },
     Or { rd: u8, rs1: u8, rs2: u8 },
     And { rd: u8, rs1: u8, rs2: u8 },
+
+    Mul { rd: u8, rs1: u8, rs2: u8 },
+    Mulh { rd: u8, rs1: u8, rs2: u8 },
+    Mulhsu { rd: u8, rs1: u8, rs2: u8 },
+    Mulhu { rd: u8, rs1: u8, rs2: u8 },
+    Div { rd: u8, rs1: u8, rs2: u8 },
+    Divu { rd: u8, rs1: u8, rs2: u8 },
+    Rem { rd: u8, rs1: u8, rs2: u8 },
+    Remu { rd: u8, rs1: u8, rs2: u8 },
+
+    Addi { rd: u8, rs1: u8, imm: i32 },
+    Slti { rd: u8, rs1: u8, imm: i32 },
+    Sltiu { rd: u8, rs1: u8, imm: i32 },
+    Xori { rd: u8, rs1: u8, imm: i32 },
+    Ori { rd: u8, rs1: u8, imm: i32 },
+    Andi { rd: u8, rs1: u8, imm: i32 },
+    Slli { rd: u8, rs1: u8, shamt: u8 },
+    Srli { rd: u8, rs1: u8, shamt: u8 },
+    Srai { rd: u8, rs1: u8, shamt: u8 },
+
+    Lb { rd: u8, rs1: u8, imm: i32 },
+    Lh { rd: u8, rs1: u8, imm: i32 },
+    Lw { rd: u8, rs1: u8, imm: i32 },
+    Lbu { rd: u8, rs1: u8, imm: i32 },
+    Lhu { rd: u8, rs1: u8, imm: i32 },
+
+    Sb { rs1: u8, rs2: u8, imm: i32 },
+    Sh { rs1: u8, rs2: u8, imm: i32 },
+    Sw { rs1: u8, rs2: u8, imm: i32 },
+
+    Beq { rs1: u8, rs2: u8, imm: i32 },
+    Bne { rs1: u8, rs2: u8, imm: i32 },
+    Blt { rs1: u8, rs2: u8, imm: i32 },
+    Bge { rs1: u8, rs2: u8, imm: i32 },
+    Bltu { rs1: u8, rs2: u8, imm: i32 },
+    Bgeu { rs1: u8, rs2: u8, imm: i32 },
+
+    Jal { rd: u8, imm: i32 },
+    Jalr { rd: u8, rs1: u8, imm: i32 },
+
+    Lui { rd: u8, imm: i32 },
+    Auipc { rd: u8, imm: i32 },
+
+    Fence { pred: u8, succ: u8 },
+    Ecall,
+    Ebreak,
+    Mret,
+
+    Csrrw { rd: u8, rs1: u8, csr: u16 },
+    Csrrs { rd: u8, rs1: u8, csr: u16 },
+    Csrrc { rd: u8, rs1: u8, csr: u16 },
+    Csrrwi { rd: u8, uimm: u8, csr: u16 },
+    Csrrsi { rd: u8, uimm: u8, csr: u16 },
+    Csrrci { rd: u8, uimm: u8, csr: u16 },
+}
+
+impl Instruction {
+    pub fn mnemonic(&self) -> &'static str {
+        match self {
+            Instruction::Add { .. } => "add",
+            Instruction::Sub { .. } => "sub",
+            Instruction::Sll { .. } => "sll",
+            Instruction::Slt { .. } => "slt",
+            Instruction::Sltu { .. } => "sltu",
+            Instruction::Xor { .. } => "xor",
+            Instruction::Srl { .. } => "srl",
+            Instruction::Sra { .. } => "sra",
+            Instruction::Or { .. } => "or",
+            Instruction::And { .. } => "and",
+            Instruction::Mul { .. } => "mul",
+            Instruction::Mulh { .. } => "mulh",
+            Instruction::Mulhsu { .. } => "mulhsu",
+            Instruction::Mulhu { .. } => "mulhu",
+            Instruction::Div { .. } => "div",
+            Instruction::Divu { .. } => "divu",
+            Instruction::Rem { .. } => "rem",
+            Instruction::Remu { .. } => "remu",
+            Instruction::Addi { .. } => "addi",
+            Instruction::Slti { .. } => "slti",
+            Instruction::Sltiu { .. } => "sltiu",
+            Instruction::Xori { .. } => "xori",
+            Instruction::Ori { .. } => "ori",
+            Instruction::Andi { .. } => "andi",
+            Instruction::Slli { .. } => "slli",
+            Instruction::Srli { .. } => "srli",
+            Instruction::Srai { .. } => "srai",
+            Instruction::Lb { .. } => "lb",
+            Instruction::Lh { .. } => "lh",
+            Instruction::Lw { .. } => "lw",
+            Instruction::Lbu { .. } => "lbu",
+            Instruction::Lhu { .. } => "lhu",
+            Instruction::Sb { .. } => "sb",
+            Instruction::Sh { .. } => "sh",
+            Instruction::Sw { .. } => "sw",
+            Instruction::Beq { .. } => "beq",
+            Instruction::Bne { .. } => "bne",
+            Instruction::Blt { .. } => "blt",
+            Instruction::Bge { .. } => "bge",
+            Instruction::Bltu { .. } => "bltu",
+            Instruction::Bgeu { .. } => "bgeu",
+            Instruction::Jal { .. } => "jal",
+            Instruction::Jalr { .. } => "jalr",
+            Instruction::Lui { .. } => "lui",
+            Instruction::Auipc { .. } => "auipc",
+            Instruction::Fence { .. } => "fence",
+            Instruction::Ecall => "ecall",
+            Instruction::Ebreak => "ebreak",
+            Instruction::Mret => "mret",
+            Instruction::Csrrw { .. } => "csrrw",
+            Instruction::Csrrs { .. } => "csrrs",
+            Instruction::Csrrc { .. } => "csrrc",
+            Instruction::Csrrwi { .. } => "csrrwi",
+            Instruction::Csrrsi { .. } => "csrrsi",
+            Instruction::Csrrci { .. } => "csrrci",
+        }
+    }
 }
@@ -1,3 +1,14 @@
+pub mod asm;
+pub mod bus;
+pub mod compressed;
+pub mod cpu;
+pub mod csr;
 pub mod decoder;
+pub mod disasm;
+pub mod elf;
+pub mod hybrid;
 pub mod instructions;
+pub mod spiking_alu;
+pub mod spiking_regfile;
 pub mod templates;
+pub mod trace;
@@ -0,0 +1,565 @@
+//! Spiking implementations of the RV32 ALU operations.
+//!
+//! Every circuit is synchronous and built from threshold gates. A gate is a
+//! single neuron with `tau_m == dt`, so its membrane forgets everything
+//! between ticks and it fires iff the excitatory units arriving on one tick,
+//! minus the inhibitory units arriving on the same tick, reach its threshold.
+//! Synaptic delays are chosen so that every input of a gate lands on the same
+//! tick, whatever the depth of the path it came from.
+//!
+//! Spike encoding: each input bit owns one neuron. A `1` is a single spike on
+//! that neuron at tick 0 and a `0` is silence. An output bit reads as `1` iff
+//! its neuron spikes at least once before the circuit's `depth` ticks elapse.
+
+use crate::{
+    core::instructions::Instruction,
+    neuro::{
+        motifs::{ConnectionSpec, InputSpec, convergent_excitation, lateral_inhibition},
+        network::Network,
+        neuron::{NeuronConfig, NeuronId, NeuronKind},
+    },
+};
+
+pub const XLEN: usize = 32;
+
+/// Global timestep every circuit is simulated with (ms per tick).
+pub const DT: f64 = 1.0;
+
+/// Membrane deflection from rest contributed by one unit of input (mV).
+const UNIT_MV: f64 = 10.0;
+
+/// Synaptic time constant; short enough that conductance left over from the
+/// previous tick is negligible next to the drive of the current one.
+const TAU_SYN: f64 = 0.25;
+
+/// Gate neuron that fires iff at least `threshold` net units arrive together.
+pub fn gate_config(threshold: u32) -> NeuronConfig {
+    let defaults = NeuronConfig::default();
+
+    NeuronConfig {
+        v_reset: defaults.v_rest,
+        tau_m: DT,
+        theta: defaults.v_rest + UNIT_MV * (threshold as f64 - 0.5),
+        refractory_period: 1,
+        tau_syn: TAU_SYN,
+        ..defaults
+    }
+}
+
+/// Fraction of a synaptic weight that is still present when the membrane
+/// integrates it (`Network::tick` decays conductances before integrating).
+fn synaptic_gain() -> f64 {
+    (-DT / TAU_SYN).exp()
+}
+
+pub fn excitatory(units: f64, delay: u32) -> ConnectionSpec {
+    let cfg = gate_config(1);
+
+    ConnectionSpec {
+        weight: units * UNIT_MV / ((cfg.e_exc - cfg.v_rest) * synaptic_gain()),
+        delay,
+    }
+}
+
+pub fn inhibitory(units: f64, delay: u32) -> ConnectionSpec {
+    let cfg = gate_config(1);
+
+    ConnectionSpec {
+        weight: -units * UNIT_MV / ((cfg.v_rest - cfg.e_inh) * synaptic_gain()),
+        delay,
+    }
+}
+
+/// A neuron together with the tick on which it fires when its bit is `1`.
+#[derive(Clone, Copy, Debug)]
+struct Signal {
+    id: NeuronId,
+    tick: u32,
+}
+
+fn latest(inputs: &[Signal]) -> u32 {
+    inputs.iter().map(|s| s.tick).max().unwrap_or(0)
+}
+
+fn input(network: &mut Network) -> Signal {
+    let id = network.add_neuron(NeuronKind::Excitatory, gate_config(1));
+    Signal { id, tick: 0 }
+}
+
+/// Threshold gate: AND for `threshold == inputs.len()`, OR for `1`, majority
+/// for `2` of 3.
+fn gate(network: &mut Network, inputs: &[Signal], threshold: u32) -> anyhow::Result<Signal> {
+    let tick = latest(inputs) + 1;
+
+    let specs = inputs
+        .iter()
+        .map(|s| InputSpec {
+            id: s.id,
+            connection: excitatory(1.0, tick - s.tick),
+        })
+        .collect::<Vec<_>>();
+
+    let id = convergent_excitation(network, specs, gate_config(threshold))?;
+
+    Ok(Signal { id, tick })
+}
+
+/// Odd parity of two or three inputs (XOR / full-adder sum). An inhibitory
+/// majority detector vetoes the output with two units, so the output sees
+/// `n - 2 * (n >= 2)` units. Fires one tick later than a plain gate.
+fn parity(network: &mut Network, inputs: &[Signal]) -> anyhow::Result<Signal> {
+    anyhow::ensure!(inputs.len() <= 3, "parity gate takes at most 3 inputs");
+
+    let veto_tick = latest(inputs) + 1;
+    let tick = veto_tick + 1;
+
+    let specs = inputs
+        .iter()
+        .map(|s| InputSpec {
+            id: s.id,
+            connection: excitatory(1.0, tick - s.tick),
+        })
+        .collect::<Vec<_>>();
+
+    let out = convergent_excitation(network, specs, gate_config(1))?;
+
+    let majority = inputs
+        .iter()
+        .map(|s| (s.id, excitatory(1.0, veto_tick - s.tick)))
+        .collect();
+
+    lateral_inhibition(
+        network,
+        majority,
+        vec![(out, inhibitory(2.0, 1))],
+        gate_config(2),
+    )?;
+
+    Ok(Signal { id: out, tick })
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BitwiseOp {
+    And,
+    Or,
+    Xor,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ShiftKind {
+    Sll,
+    Srl,
+    Sra,
+}
+
+#[derive(Debug)]
+pub struct Circuit {
+    pub network: Network,
+    pub inputs: Vec<NeuronId>,
+    pub outputs: Vec<NeuronId>,
+    pub depth: u32,
+}
+
+#[derive(Debug)]
+pub struct CircuitRun {
+    pub outputs: Vec<bool>,
+    /// Ticks until the last spike anywhere in the circuit.
+    pub ticks: u32,
+    pub spikes: usize,
+}
+
+impl Circuit {
+    fn new(network: Network, inputs: &[Signal], outputs: &[Signal]) -> Self {
+        let mut network = network;
+        network.resize_events();
+
+        Self {
+            network,
+            inputs: inputs.iter().map(|s| s.id).collect(),
+            outputs: outputs.iter().map(|s| s.id).collect(),
+            depth: latest(outputs) + 1,
+        }
+    }
+
+    pub fn evaluate(&mut self, inputs: &[bool]) -> anyhow::Result<CircuitRun> {
+        anyhow::ensure!(
+            inputs.len() == self.inputs.len(),
+            "circuit takes {} input bits, got {}",
+            self.inputs.len(),
+            inputs.len()
+        );
+
+        self.network.reset();
+
+        let drive = excitatory(2.0, 0).weight;
+        for (&id, &bit) in self.inputs.iter().zip(inputs) {
+            if bit {
+                self.network.schedule_spike(id, drive, 0);
+            }
+        }
+
+        let mut fired = vec![false; self.network.neurons.len()];
+        let mut spikes = 0;
+        let mut ticks = 0;
+
+        for tick in 0..self.depth {
+            self.network.tick(DT);
+
+            for &id in &self.network.spiked {
+                fired[id] = true;
+                spikes += 1;
+                ticks = tick + 1;
+            }
+        }
+
+        Ok(CircuitRun {
+            outputs: self.outputs.iter().map(|&id| fired[id]).collect(),
+            ticks,
+            spikes,
+        })
+    }
+}
+
+/// Inputs: `a[0..32]`, `b[0..32]`. Outputs: `a op b`, LSB first.
+pub fn bitwise_circuit(op: BitwiseOp) -> anyhow::Result<Circuit> {
+    let mut network = Network::new();
+
+    let a: Vec<Signal> = (0..XLEN).map(|_| input(&mut network)).collect();
+    let b: Vec<Signal> = (0..XLEN).map(|_| input(&mut network)).collect();
+
+    let outputs = a
+        .iter()
+        .zip(&b)
+        .map(|(&a, &b)| match op {
+            BitwiseOp::And => gate(&mut network, &[a, b], 2),
+            BitwiseOp::Or => gate(&mut network, &[a, b], 1),
+            BitwiseOp::Xor => parity(&mut network, &[a, b]),
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    let inputs = [a, b].concat();
+
+    Ok(Circuit::new(network, &inputs, &outputs))
+}
+
+/// Ripple-carry adder/subtractor.
+///
+/// Inputs: `a[0..32]`, `b[0..32]`, `sub`, `one`. `one` is a bias line that
+/// must always be driven. With `sub` set, `b` is inverted by an XOR layer and
+/// `sub` doubles as the carry-in, giving `a - b`.
+///
+/// Outputs: `sum[0..32]`, then `sltu` (`!carry_out`) and `slt`
+/// (`a[31] ^ !b[31] ^ carry_out`), both only meaningful with `sub` set.
+pub fn adder_circuit() -> anyhow::Result<Circuit> {
+    let mut network = Network::new();
+
+    let a: Vec<Signal> = (0..XLEN).map(|_| input(&mut network)).collect();
+    let b: Vec<Signal> = (0..XLEN).map(|_| input(&mut network)).collect();
+    let sub = input(&mut network);
+    let one = input(&mut network);
+
+    let b_eff = b
+        .iter()
+        .map(|&b| parity(&mut network, &[b, sub]))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    let mut carry = sub;
+    let mut outputs = Vec::with_capacity(XLEN + 2);
+
+    for (&a, &b) in a.iter().zip(&b_eff) {
+        outputs.push(parity(&mut network, &[a, b, carry])?);
+        carry = gate(&mut network, &[a, b, carry], 2)?;
+    }
+
+    outputs.push(parity(&mut network, &[one, carry])?);
+    outputs.push(parity(
+        &mut network,
+        &[a[XLEN - 1], b_eff[XLEN - 1], carry],
+    )?);
+
+    let inputs = [a, b, vec![sub, one]].concat();
+
+    Ok(Circuit::new(network, &inputs, &outputs))
+}
+
+/// Logarithmic barrel shifter.
+///
+/// Inputs: `x[0..32]`, `shamt[0..5]`. Outputs: `x` shifted by `shamt`.
+///
+/// Stage `k` shifts by `2^k` when `shamt[k]` is set. Each output bit is
+/// `pass | move`, where `pass` keeps the bit unless a shared inhibitory
+/// interneuron driven by `shamt[k]` vetoes the whole stage, and `move` is
+/// `source & shamt[k]`.
+pub fn shifter_circuit(kind: ShiftKind) -> anyhow::Result<Circuit> {
+    let mut network = Network::new();
+
+    let x: Vec<Signal> = (0..XLEN).map(|_| input(&mut network)).collect();
+    let shamt: Vec<Signal> = (0..5).map(|_| input(&mut network)).collect();
+
+    let mut stage = x.clone();
+
+    for (k, &select) in shamt.iter().enumerate() {
+        let distance = 1 << k;
+        let veto_tick = latest(&stage).max(select.tick) + 1;
+        let tick = veto_tick + 1;
+
+        let pass = stage
+            .iter()
+            .map(|s| {
+                let spec = InputSpec {
+                    id: s.id,
+                    connection: excitatory(1.0, tick - s.tick),
+                };
+                let id = convergent_excitation(&mut network, [spec], gate_config(1))?;
+                Ok(Signal { id, tick })
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        lateral_inhibition(
+            &mut network,
+            vec![(select.id, excitatory(1.0, veto_tick - select.tick))],
+            pass.iter().map(|s| (s.id, inhibitory(2.0, 1))).collect(),
+            gate_config(1),
+        )?;
+
+        stage = (0..XLEN)
+            .map(|j| {
+                let source = match kind {
+                    ShiftKind::Sll => j.checked_sub(distance),
+                    ShiftKind::Srl => Some(j + distance).filter(|&i| i < XLEN),
+                    ShiftKind::Sra => Some((j + distance).min(XLEN - 1)),
+                };
+
+                match source {
+                    Some(i) => {
+                        let moved = gate(&mut network, &[stage[i], select], 2)?;
+                        gate(&mut network, &[pass[j], moved], 1)
+                    }
+                    None => gate(&mut network, &[pass[j]], 1),
+                }
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+    }
+
+    let inputs = [x, shamt].concat();
+
+    Ok(Circuit::new(network, &inputs, &stage))
+}
+
+#[derive(Debug)]
+pub struct AluRun {
+    pub value: u32,
+    pub ticks: u32,
+    pub spikes: usize,
+    pub neurons: usize,
+}
+
+/// Executes ALU instructions on prebuilt spiking circuits.
+#[derive(Debug)]
+pub struct SpikingAlu {
+    and: Circuit,
+    or: Circuit,
+    xor: Circuit,
+    adder: Circuit,
+    sll: Circuit,
+    srl: Circuit,
+    sra: Circuit,
+}
+
+/// Turns a circuit's output bits into the instruction's result.
+type Readout = fn(&[bool]) -> u32;
+
+fn bits(value: u32, width: usize) -> impl Iterator<Item = bool> {
+    (0..width).map(move |i| (value >> i) & 1 == 1)
+}
+
+fn word(bits: &[bool]) -> u32 {
+    bits.iter()
+        .enumerate()
+        .fold(0, |acc, (i, &bit)| acc | ((bit as u32) << i))
+}
+
+impl SpikingAlu {
+    pub fn new() -> anyhow::Result<Self> {
+        Ok(Self {
+            and: bitwise_circuit(BitwiseOp::And)?,
+            or: bitwise_circuit(BitwiseOp::Or)?,
+            xor: bitwise_circuit(BitwiseOp::Xor)?,
+            adder: adder_circuit()?,
+            sll: shifter_circuit(ShiftKind::Sll)?,
+            srl: shifter_circuit(ShiftKind::Srl)?,
+            sra: shifter_circuit(ShiftKind::Sra)?,
+        })
+    }
+
+    /// Same contract as `cpu::alu`: `b` is rs2, the sign-extended immediate or
+    /// the shift amount.
+    pub fn execute(&mut self, instruction: &Instruction, a: u32, b: u32) -> anyhow::Result<AluRun> {
+        let adder_inputs = |sub: bool| {
+            bits(a, XLEN)
+                .chain(bits(b, XLEN))
+                .chain([sub, true])
+                .collect::<Vec<_>>()
+        };
+        let operand_inputs = || bits(a, XLEN).chain(bits(b, XLEN)).collect::<Vec<_>>();
+        let shift_inputs = || bits(a, XLEN).chain(bits(b, 5)).collect::<Vec<_>>();
+
+        let (circuit, inputs, read): (&mut Circuit, Vec<bool>, Readout) = match instruction {
+            Instruction::Add { .. } | Instruction::Addi { .. } => {
+                (&mut self.adder, adder_inputs(false), |o| word(&o[..XLEN]))
+            }
+            Instruction::Sub { .. } => (&mut self.adder, adder_inputs(true), |o| word(&o[..XLEN])),
+            Instruction::Sltu { .. } | Instruction::Sltiu { .. } => {
+                (&mut self.adder, adder_inputs(true), |o| o[XLEN] as u32)
+            }
+            Instruction::Slt { .. } | Instruction::Slti { .. } => {
+                (&mut self.adder, adder_inputs(true), |o| o[XLEN + 1] as u32)
+            }
+            Instruction::And { .. } | Instruction::Andi { .. } => {
+                (&mut self.and, operand_inputs(), word)
+            }
+            Instruction::Or { .. } | Instruction::Ori { .. } => {
+                (&mut self.or, operand_inputs(), word)
+            }
+            Instruction::Xor { .. } | Instruction::Xori { .. } => {
+                (&mut self.xor, operand_inputs(), word)
+            }
+            Instruction::Sll { .. } | Instruction::Slli { .. } => {
+                (&mut self.sll, shift_inputs(), word)
+            }
+            Instruction::Srl { .. } | Instruction::Srli { .. } => {
+                (&mut self.srl, shift_inputs(), word)
+            }
+            Instruction::Sra { .. } | Instruction::Srai { .. } => {
+                (&mut self.sra, shift_inputs(), word)
+            }
+            _ => anyhow::bail!("instruction does not use the ALU"),
+        };
+
+        let run = circuit.evaluate(&inputs)?;
+
+        Ok(AluRun {
+            value: read(&run.outputs),
+            ticks: run.ticks,
+            spikes: run.spikes,
+            neurons: circuit.network.neurons.len(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{Rng, SeedableRng, rngs::StdRng};
+
+    use super::*;
+    use crate::core::cpu::alu;
+
+    const OPS: [Instruction; 10] = [
+        Instruction::Add {
+            rd: 1,
+            rs1: 2,
+            rs2: 3,
+        },
+        Instruction::Sub {
+            rd: 1,
+            rs1: 2,
+            rs2: 3,
+        },
+        Instruction::Sll {
+            rd: 1,
+            rs1: 2,
+            rs2: 3,
+        },
+        Instruction::Slt {
+            rd: 1,
+            rs1: 2,
+            rs2: 3,
+        },
+        Instruction::Sltu {
+            rd: 1,
+            rs1: 2,
+            rs2: 3,
+        },
+        Instruction::Xor {
+            rd: 1,
+            rs1: 2,
+            rs2: 3,
+        },
+        Instruction::Srl {
+            rd: 1,
+            rs1: 2,
+            rs2: 3,
+        },
+        Instruction::Sra {
+            rd: 1,
+            rs1: 2,
+            rs2: 3,
+        },
+        Instruction::Or {
+            rd: 1,
+            rs1: 2,
+            rs2: 3,
+        },
+        Instruction::And {
+            rd: 1,
+            rs1: 2,
+            rs2: 3,
+        },
+    ];
+
+    const SPECIAL: [u32; 9] = [
+        0,
+        1,
+        u32::MAX,
+        i32::MIN as u32,
+        i32::MAX as u32,
+        31,
+        32,
+        0x5555_5555,
+        0xaaaa_aaaa,
+    ];
+
+    fn check(spiking: &mut SpikingAlu, op: &Instruction, a: u32, b: u32) {
+        let run = spiking.execute(op, a, b).unwrap();
+        assert_eq!(
+            run.value,
+            alu(op, a, b).unwrap(),
+            "{} {a:#010x}, {b:#010x}",
+            op.mnemonic()
+        );
+    }
+
+    #[test]
+    fn matches_the_reference_alu_on_special_operands() {
+        let mut spiking = SpikingAlu::new().unwrap();
+        for op in &OPS {
+            for &a in &SPECIAL {
+                for &b in &SPECIAL {
+                    check(&mut spiking, op, a, b);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn matches_the_reference_alu_on_random_operands() {
+        let mut spiking = SpikingAlu::new().unwrap();
+        let mut rng = StdRng::seed_from_u64(4);
+        for op in &OPS {
+            for _ in 0..100 {
+                check(&mut spiking, op, rng.random(), rng.random());
+            }
+        }
+    }
+
+    #[test]
+    fn leaves_multiply_and_divide_to_the_reference() {
+        let mut spiking = SpikingAlu::new().unwrap();
+        let mul = Instruction::Mul {
+            rd: 1,
+            rs1: 2,
+            rs2: 3,
+        };
+        assert!(spiking.execute(&mul, 3, 4).is_err());
+    }
+}
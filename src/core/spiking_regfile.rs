@@ -0,0 +1,346 @@
+//! Register file whose bits are held by self-sustaining spiking latches.
+//!
+//! Each bit is a small population of gate neurons (see `spiking_alu`) wired
+//! all-to-all by `recurrent_excitation` with a two-tick delay. Once driven,
+//! the population keeps itself firing every other tick, which is exactly the
+//! gate refractory period; a silent population stays silent. A `1` is a
+//! firing population and a `0` a silent one.
+//!
+//! Every port operation starts on an even tick, so latches only ever fire on
+//! even ticks. Every register has three kinds of port neuron, all driven by
+//! `StimulusRunner` pulses:
+//!
+//! - `clear` drives an inhibitory interneuron that vetoes the register's
+//!   cells on the next even tick.
+//! - `set[bit]` ignites the bit's cell on the even tick after that.
+//! - `enable` opens one read gate per bit. A read gate fires iff it is
+//!   enabled and at least half of its cell fires into it.
+//!
+//! Cell neurons have a much deeper `e_inh` than other gates. Below `e_inh`
+//! inhibition turns depolarizing, and with `tau_m == dt` a silent cell that a
+//! veto pushed past it would rebound into a spike on the next tick.
+//!
+//! Noise is an independent drive, uniform in `[-noise, noise]` units, that
+//! every cell neuron receives on every tick.
+
+use rand::{Rng, SeedableRng, rngs::StdRng};
+
+use crate::{
+    core::spiking_alu::{DT, XLEN, excitatory, gate_config, inhibitory},
+    neuro::{
+        motifs::{
+            ConnectionSpec, InputSpec, convergent_excitation, lateral_inhibition,
+            recurrent_excitation,
+        },
+        network::Network,
+        neuron::{NeuronConfig, NeuronId, NeuronKind},
+        stimuli::{StimulusMode, StimulusRunner, StimulusSpec},
+    },
+};
+
+/// x0 is hardwired to zero and has no cells.
+const REGISTERS: usize = 31;
+
+/// Ticks from the port pulses until a written value is latched, rounded up
+/// to keep the next operation on an even tick.
+const WRITE_TICKS: u32 = 6;
+/// Ticks from the enable pulse until the read gates have fired.
+const READ_TICKS: u32 = 2;
+
+/// Total drive a full latch population feeds back into each of its neurons.
+const LATCH_UNITS: f64 = 2.0;
+/// Enough to silence a neuron receiving the full latch drive.
+const VETO_UNITS: f64 = LATCH_UNITS + 0.5;
+/// Inhibitory reversal potential of cell neurons (mV).
+const CELL_E_INH: f64 = -120.0;
+
+#[derive(Clone, Copy, Debug)]
+pub struct RegisterFileParams {
+    /// Neurons per bit; at least 2.
+    pub population: usize,
+    /// Amplitude of the per-tick noise drive, in threshold units.
+    pub noise: f64,
+    pub seed: u64,
+}
+
+impl Default for RegisterFileParams {
+    fn default() -> Self {
+        Self {
+            population: 3,
+            noise: 0.0,
+            seed: 0,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Register {
+    clear: NeuronId,
+    set: Vec<NeuronId>,
+    enable: NeuronId,
+    read: Vec<NeuronId>,
+}
+
+#[derive(Debug)]
+pub struct SpikingRegisterFile {
+    pub network: Network,
+    stimuli: StimulusRunner,
+    registers: Vec<Register>,
+    cells: Vec<NeuronId>,
+    noise: f64,
+    rng: StdRng,
+    pub reads: u64,
+    pub writes: u64,
+    pub ticks: u64,
+}
+
+fn port(network: &mut Network) -> NeuronId {
+    network.add_neuron(NeuronKind::Excitatory, gate_config(1))
+}
+
+fn cell_config() -> NeuronConfig {
+    NeuronConfig {
+        e_inh: CELL_E_INH,
+        ..gate_config(1)
+    }
+}
+
+/// `inhibitory` rescaled for the deeper reversal potential of cell neurons.
+fn cell_inhibitory(units: f64, delay: u32) -> ConnectionSpec {
+    let gate = gate_config(1);
+    let spec = inhibitory(units, delay);
+
+    ConnectionSpec {
+        weight: spec.weight * (gate.v_rest - gate.e_inh) / (gate.v_rest - CELL_E_INH),
+        ..spec
+    }
+}
+
+impl SpikingRegisterFile {
+    pub fn new(params: &RegisterFileParams) -> anyhow::Result<Self> {
+        anyhow::ensure!(
+            params.population >= 2,
+            "a latch needs at least 2 neurons per bit"
+        );
+        anyhow::ensure!(
+            params.noise.is_finite() && params.noise >= 0.0,
+            "noise must be finite and >= 0"
+        );
+
+        let mut network = Network::new();
+        let mut registers = Vec::with_capacity(REGISTERS);
+        let mut cells = Vec::new();
+
+        for _ in 0..REGISTERS {
+            let clear = port(&mut network);
+            let enable = port(&mut network);
+            let mut set = Vec::with_capacity(XLEN);
+            let mut read = Vec::with_capacity(XLEN);
+            let mut register_cells = Vec::with_capacity(XLEN * params.population);
+
+            for _ in 0..XLEN {
+                let cell: Vec<NeuronId> = (0..params.population)
+                    .map(|_| network.add_neuron(NeuronKind::Excitatory, cell_config()))
+                    .collect();
+
+                let share = LATCH_UNITS / (params.population - 1) as f64;
+                let latch = cell
+                    .iter()
+                    .map(|&id| InputSpec {
+                        id,
+                        connection: excitatory(share, 2),
+                    })
+                    .collect::<Vec<_>>();
+                recurrent_excitation(&mut network, &latch)?;
+
+                let bit_set = port(&mut network);
+                for &id in &cell {
+                    let ignite = excitatory(2.0, 4);
+                    network.connect(bit_set, id, ignite.weight, ignite.delay)?;
+                }
+
+                let share = 1.0 / params.population as f64;
+                let sense = cell.iter().map(|&id| InputSpec {
+                    id,
+                    connection: excitatory(share, 1),
+                });
+                let enabled = InputSpec {
+                    id: enable,
+                    connection: excitatory(1.0, 1),
+                };
+                let bit_read =
+                    convergent_excitation(&mut network, sense.chain([enabled]), gate_config(2))?;
+
+                set.push(bit_set);
+                read.push(bit_read);
+                register_cells.extend(cell);
+            }
+
+            let targets = register_cells
+                .iter()
+                .map(|&id| (id, cell_inhibitory(VETO_UNITS, 1)))
+                .collect::<Vec<(NeuronId, ConnectionSpec)>>();
+            lateral_inhibition(
+                &mut network,
+                vec![(clear, excitatory(1.0, 1))],
+                targets,
+                gate_config(1),
+            )?;
+
+            registers.push(Register {
+                clear,
+                set,
+                enable,
+                read,
+            });
+            cells.extend(register_cells);
+        }
+
+        network.resize_events();
+
+        Ok(Self {
+            network,
+            stimuli: StimulusRunner::new(DT),
+            registers,
+            cells,
+            noise: params.noise,
+            rng: StdRng::seed_from_u64(params.seed),
+            reads: 0,
+            writes: 0,
+            ticks: 0,
+        })
+    }
+
+    fn pulse(&mut self, neuron: NeuronId) {
+        let spec = StimulusSpec {
+            mode: StimulusMode::ManualPulse {
+                amplitude: excitatory(2.0, 0).weight,
+            },
+            enabled: true,
+        };
+        self.stimuli
+            .fire(neuron as u64, neuron, &spec, &self.network);
+    }
+
+    /// Advances the network, returning which of `watch` spiked.
+    fn run(&mut self, ticks: u32, watch: &[NeuronId]) -> Vec<bool> {
+        let mut fired = vec![false; watch.len()];
+
+        for _ in 0..ticks {
+            self.stimuli.apply(&mut self.network);
+
+            if self.noise > 0.0 {
+                for &id in &self.cells {
+                    let units = self.rng.random_range(-self.noise..=self.noise);
+                    let weight = if units >= 0.0 {
+                        excitatory(units, 0).weight
+                    } else {
+                        cell_inhibitory(-units, 0).weight
+                    };
+                    self.network.schedule_spike(id, weight, 0);
+                }
+            }
+
+            self.network.tick(DT);
+            self.ticks += 1;
+
+            for (fired, id) in fired.iter_mut().zip(watch) {
+                if self.network.spiked.contains(id) {
+                    *fired = true;
+                }
+            }
+        }
+
+        fired
+    }
+
+    /// Lets the latches hold their values for `ticks` ticks.
+    pub fn idle(&mut self, ticks: u32) {
+        self.run(ticks, &[]);
+    }
+
+    fn align(&mut self) {
+        if self.network.t % 2 == 1 {
+            self.idle(1);
+        }
+    }
+
+    pub fn write(&mut self, reg: u8, value: u32) {
+        let Some(index) = (reg as usize).checked_sub(1) else {
+            return;
+        };
+
+        self.align();
+        self.pulse(self.registers[index].clear);
+        for bit in 0..XLEN {
+            if (value >> bit) & 1 == 1 {
+                self.pulse(self.registers[index].set[bit]);
+            }
+        }
+
+        self.run(WRITE_TICKS, &[]);
+        self.writes += 1;
+    }
+
+    pub fn read(&mut self, reg: u8) -> u32 {
+        let Some(index) = (reg as usize).checked_sub(1) else {
+            return 0;
+        };
+
+        self.align();
+        self.pulse(self.registers[index].enable);
+        let read = self.registers[index].read.clone();
+        let fired = self.run(READ_TICKS, &read);
+        self.reads += 1;
+
+        fired
+            .iter()
+            .enumerate()
+            .fold(0, |acc, (i, &bit)| acc | ((bit as u32) << i))
+    }
+}
+
+/// Bit errors after holding random values under noise.
+#[derive(Clone, Copy, Debug)]
+pub struct Retention {
+    pub noise: f64,
+    pub bits: usize,
+    /// Stored `1`s read back as `0`.
+    pub lost: usize,
+    /// Stored `0`s read back as `1`.
+    pub gained: usize,
+}
+
+impl Retention {
+    pub fn reliability(&self) -> f64 {
+        1.0 - (self.lost + self.gained) as f64 / self.bits as f64
+    }
+}
+
+/// Writes a random word to every register, holds them for `hold` ticks and
+/// reads them back, with noise on throughout.
+pub fn measure_retention(params: &RegisterFileParams, hold: u32) -> anyhow::Result<Retention> {
+    let mut file = SpikingRegisterFile::new(params)?;
+    let mut rng = StdRng::seed_from_u64(params.seed ^ 0x5eed);
+
+    let stored: Vec<u32> = (0..REGISTERS).map(|_| rng.random()).collect();
+    for (reg, &value) in (1..).zip(&stored) {
+        file.write(reg, value);
+    }
+
+    file.idle(hold);
+
+    let mut retention = Retention {
+        noise: params.noise,
+        bits: REGISTERS * XLEN,
+        lost: 0,
+        gained: 0,
+    };
+    for (reg, &value) in (1..).zip(&stored) {
+        let read = file.read(reg);
+        retention.lost += (value & !read).count_ones() as usize;
+        retention.gained += (!value & read).count_ones() as usize;
+    }
+
+    Ok(retention)
+}
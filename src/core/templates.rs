@@ -9,7 +9,7 @@ use crate::neuro::{
     neuron::{NeuronConfig, NeuronId, NeuronKind},
 };
 
-#[derive(Clone)]
+#[derive(Clone, Debug)]
 pub struct CircuitParams {
     pub strong_weight: f64,
     pub inhibitory_weight: f64,
@@ -19,8 +19,8 @@ pub struct CircuitParams {
 pub fn build_sensory_circuit(
     network: &mut Network,
     params: &CircuitParams,
+    rng: &mut impl Rng,
 ) -> anyhow::Result<(NeuronId, NeuronId)> {
-    let mut rng = rand::rng();
     let default_cfg = NeuronConfig::default();
 
     let outputs: Vec<OutputSpec> = (0..3)
@@ -50,7 +50,7 @@ pub fn build_sensory_circuit(
         delay: 1,
     };
 
-    let input_id = network.add_neuron(NeuronKind::Excitatory, default_cfg);
+    let input_id = network.add_neuron(NeuronKind::Excitatory, default_cfg.clone());
 
     let processing_layer = divergent_excitation(network, input_id, outputs)?;
 
@@ -64,7 +64,7 @@ pub fn build_sensory_circuit(
         .map(|&id| (id, inhibitory_conn))
         .collect();
 
-    lateral_inhibition(network, exc_inputs, inh_targets, default_cfg)?;
+    lateral_inhibition(network, exc_inputs, inh_targets, default_cfg.clone())?;
 
     let convergence_inputs = processing_layer
         .iter()
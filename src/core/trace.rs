@@ -0,0 +1,78 @@
+use std::fmt;
+
+use crate::core::{csr, instructions::Instruction};
+
+/// Architectural state written by one instruction.
+#[derive(Clone, Copy, Debug)]
+pub enum Write {
+    Reg(u8, u32),
+    Csr(u16, u32),
+}
+
+/// Record of one retired instruction.
+#[derive(Clone, Debug)]
+pub struct Commit {
+    pub pc: u32,
+    /// Raw encoding, zero-extended when `len` is 2.
+    pub word: u32,
+    pub len: u32,
+    pub instruction: Instruction,
+    /// Register and CSR writes in the order they happened.
+    pub writes: Vec<Write>,
+    pub loads: Vec<u32>,
+    /// Address, value and width in bytes.
+    pub stores: Vec<(u32, u32, usize)>,
+}
+
+impl Commit {
+    pub fn new(pc: u32, word: u32, len: u32, instruction: Instruction) -> Self {
+        Self {
+            pc,
+            word,
+            len,
+            instruction,
+            writes: Vec::new(),
+            loads: Vec::new(),
+            stores: Vec::new(),
+        }
+    }
+}
+
+/// Hex value padded to `bytes` bytes, as Spike prints it.
+fn hex(value: u32, bytes: usize) -> String {
+    format!("0x{value:0width$x}", width = bytes * 2)
+}
+
+/// Spike's `--log-commits` layout for hart 0 in machine mode, followed by the
+/// disassembly as a `#` comment. Stripping the comment gives a line that
+/// diffs cleanly against Spike.
+impl fmt::Display for Commit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "core   0: 3 {} ({})",
+            hex(self.pc, 4),
+            hex(self.word, self.len as usize)
+        )?;
+
+        for write in &self.writes {
+            match *write {
+                Write::Reg(reg, value) => write!(f, " x{reg:<2} {}", hex(value, 4))?,
+                Write::Csr(addr, value) => write!(
+                    f,
+                    " c{addr}_{} {}",
+                    csr::name(addr).unwrap_or("unknown"),
+                    hex(value, 4)
+                )?,
+            }
+        }
+        for addr in &self.loads {
+            write!(f, " mem {}", hex(*addr, 4))?;
+        }
+        for &(addr, value, len) in &self.stores {
+            write!(f, " mem {} {}", hex(addr, 4), hex(value, len))?;
+        }
+
+        write!(f, "  # {}", self.instruction)
+    }
+}
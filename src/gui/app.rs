@@ -1,9 +1,7 @@
-use std::collections::HashMap;
 use std::fs;
 
 use egui_plot::{Line, Plot, PlotPoints};
 use egui_snarl::ui::{SnarlStyle, SnarlWidget};
-use serde::{Deserialize, Serialize};
 
 use crate::gui::builder::stimulus_body;
 use crate::gui::{
@@ -11,9 +9,14 @@ use crate::gui::{
     compiler::{CompiledGraph, compile_snarl_to_network},
     editor::GraphViewer,
     layout::{draw_snarl_topology, get_neuron_color},
+    snapshot::{GraphSnapshot, GraphSnapshotRef},
 };
-use crate::neuro::stimuli::{StimulusRunner, StimulusSpec};
-use crate::neuro::{motifs::ConnectionSpec, neuron::NeuronId};
+use neurorisc::neuro::monitor::{StateMonitor, Variable};
+use neurorisc::neuro::neuron::NeuronId;
+use neurorisc::neuro::stimuli::{StimulusRunner, StimulusSpec};
+
+/// Voltage samples kept per neuron for the plot.
+const PLOT_SAMPLES: usize = 20_000;
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 enum Tab {
@@ -21,20 +24,7 @@ enum Tab {
     Editor,
 }
 
-#[derive(Serialize, Deserialize)]
-struct GraphSnapshot {
-    snarl: egui_snarl::Snarl<GraphNode>,
-    wires: HashMap<WireKey, ConnectionSpec>,
-}
-
-#[derive(Serialize)]
-struct GraphSnapshotRef<'a> {
-    snarl: &'a egui_snarl::Snarl<GraphNode>,
-    wires: &'a HashMap<WireKey, ConnectionSpec>,
-}
-
 pub struct App {
-    history: Vec<Vec<f64>>,
     running: bool,
     time: f64,
     dt: f64,
@@ -53,7 +43,6 @@ impl App {
     pub fn new(_cc: &eframe::CreationContext<'_>) -> Self {
         let dt = 0.1;
         let mut app = Self {
-            history: vec![],
             running: false,
             time: 0.0,
             dt,
@@ -83,8 +72,11 @@ impl App {
         });
 
         match compile_snarl_to_network(&self.editor.snarl, &self.editor.wires) {
-            Ok(compiled) => {
-                self.history = vec![Vec::new(); compiled.network.neurons.len()];
+            Ok(mut compiled) => {
+                let neurons = (0..compiled.network.neurons.len()).collect();
+                let mut monitor = StateMonitor::new(neurons, vec![Variable::V]);
+                monitor.set_capacity(Some(PLOT_SAMPLES));
+                compiled.network.monitor = Some(monitor);
                 self.compiled = Some(compiled);
                 self.editor.dirty = false;
             }
@@ -95,12 +87,7 @@ impl App {
         }
     }
 
-    fn fire_stimulus_neuron(
-        &mut self,
-        stimulus_id: u64,
-        neuron_id: NeuronId,
-        spec: &StimulusSpec,
-    ) {
+    fn fire_stimulus_neuron(&mut self, stimulus_id: u64, neuron_id: NeuronId, spec: &StimulusSpec) {
         if let Some(compiled) = self.compiled.as_ref() {
             println!("Firing {:#?}", spec.mode);
             self.stimuli
@@ -138,7 +125,9 @@ impl App {
 
         let result = fs::read_to_string(&path)
             .map_err(|err| err.to_string())
-            .and_then(|data| serde_json::from_str::<GraphSnapshot>(&data).map_err(|err| err.to_string()));
+            .and_then(|data| {
+                serde_json::from_str::<GraphSnapshot>(&data).map_err(|err| err.to_string())
+            });
 
         match result {
             Ok(snapshot) => {
@@ -165,12 +154,6 @@ impl eframe::App for App {
                 self.stimuli.apply(&mut compiled.network);
                 compiled.network.tick(self.dt);
                 self.time += self.dt;
-
-                for (i, neuron) in compiled.network.neurons.iter().enumerate() {
-                    if let Some(history) = self.history.get_mut(i) {
-                        history.push(neuron.state.v);
-                    }
-                }
             }
             ctx.request_repaint();
         }
@@ -320,12 +303,25 @@ impl eframe::App for App {
                     .show_inside(ui, |ui| {
                         let plot = Plot::new("voltage_plot").include_y(-70.0).include_y(-45.0);
 
+                        let monitor = self
+                            .compiled
+                            .as_ref()
+                            .and_then(|compiled| compiled.network.monitor.as_ref());
+
                         plot.show(ui, |plot_ui| {
-                            for (i, neuron_history) in self.history.iter().enumerate() {
-                                let points: PlotPoints = neuron_history
+                            let Some(monitor) = monitor else {
+                                return;
+                            };
+
+                            for &i in monitor.neurons() {
+                                let Some(trace) = monitor.trace(i, Variable::V) else {
+                                    continue;
+                                };
+                                let points: PlotPoints = monitor
+                                    .ticks()
                                     .iter()
-                                    .enumerate()
-                                    .map(|(t, &v)| [t as f64, v])
+                                    .zip(trace)
+                                    .map(|(&t, &v)| [t as f64, v])
                                     .collect();
 
                                 let color = get_neuron_color(i);
@@ -3,9 +3,12 @@ use std::collections::HashMap;
 use egui::Ui;
 use egui_snarl::{InPinId, OutPinId, Snarl};
 
-use crate::neuro::{
+use neurorisc::neuro::{
     motifs::ConnectionSpec,
-    neuron::{NeuronConfig, NeuronKind},
+    neuron::{
+        AdExParams, Dynamics, HodgkinHuxleyParams, IzhikevichParams, IzhikevichPreset,
+        NeuronConfig, NeuronKind,
+    },
     stimuli::{StimulusMode, StimulusSpec},
 };
 
@@ -87,6 +90,27 @@ impl Default for EditorState {
     }
 }
 
+fn config_value(ui: &mut Ui, value: &mut f64, speed: f64, prefix: &str) -> bool {
+    ui.add_sized(
+        [140.0, 20.0],
+        egui::DragValue::new(value).speed(speed).prefix(prefix),
+    )
+    .changed()
+}
+
+/// Like `config_value`, for values that are divided by and must stay
+/// positive.
+fn positive_value(ui: &mut Ui, value: &mut f64, speed: f64, prefix: &str) -> bool {
+    ui.add_sized(
+        [140.0, 20.0],
+        egui::DragValue::new(value)
+            .speed(speed)
+            .range(1e-3..=f64::INFINITY)
+            .prefix(prefix),
+    )
+    .changed()
+}
+
 pub fn neuron_body(ui: &mut Ui, spec: &mut NeuronSpec) -> bool {
     let mut changed = false;
 
@@ -105,48 +129,104 @@ pub fn neuron_body(ui: &mut Ui, spec: &mut NeuronSpec) -> bool {
             .selectable_value(&mut spec.kind, NeuronKind::Inhibitory, "Inhibitory")
             .changed();
 
+        ui.label("Model");
+        let dynamics = &mut spec.config.dynamics;
+        if ui.radio(matches!(dynamics, Dynamics::Lif), "LIF").clicked() {
+            *dynamics = Dynamics::Lif;
+            changed = true;
+        }
+        if ui
+            .radio(matches!(dynamics, Dynamics::Izhikevich(_)), "Izhikevich")
+            .clicked()
+            && !matches!(dynamics, Dynamics::Izhikevich(_))
+        {
+            *dynamics = Dynamics::Izhikevich(IzhikevichParams::default());
+            changed = true;
+        }
+        if ui
+            .radio(matches!(dynamics, Dynamics::AdEx(_)), "AdEx")
+            .clicked()
+            && !matches!(dynamics, Dynamics::AdEx(_))
+        {
+            *dynamics = Dynamics::AdEx(AdExParams::default());
+            changed = true;
+        }
+        if ui
+            .radio(
+                matches!(dynamics, Dynamics::HodgkinHuxley(_)),
+                "Hodgkin-Huxley",
+            )
+            .clicked()
+            && !matches!(dynamics, Dynamics::HodgkinHuxley(_))
+        {
+            *dynamics = Dynamics::HodgkinHuxley(HodgkinHuxleyParams::default());
+            changed = true;
+        }
+        if let Dynamics::Custom(custom) = dynamics {
+            let _ = ui.radio(true, custom.name());
+        }
+
         ui.separator();
         ui.label("Config");
-        changed |= ui
-            .add_sized(
-                [140.0, 20.0],
-                egui::DragValue::new(&mut spec.config.theta)
-                    .speed(0.1)
-                    .prefix("Theta "),
-            )
-            .changed();
-        changed |= ui
-            .add_sized(
-                [140.0, 20.0],
-                egui::DragValue::new(&mut spec.config.v_rest)
-                    .speed(0.1)
-                    .prefix("V_rest "),
-            )
-            .changed();
-        changed |= ui
-            .add_sized(
-                [140.0, 20.0],
-                egui::DragValue::new(&mut spec.config.v_reset)
-                    .speed(0.1)
-                    .prefix("V_reset "),
-            )
-            .changed();
-        changed |= ui
-            .add_sized(
-                [140.0, 20.0],
-                egui::DragValue::new(&mut spec.config.tau_m)
-                    .speed(0.1)
-                    .prefix("Tau_m "),
-            )
-            .changed();
-        changed |= ui
-            .add_sized(
-                [140.0, 20.0],
-                egui::DragValue::new(&mut spec.config.tau_syn)
-                    .speed(0.1)
-                    .prefix("Tau_syn "),
-            )
-            .changed();
+        let config = &mut spec.config;
+        if matches!(config.dynamics, Dynamics::Lif | Dynamics::Custom(_)) {
+            changed |= config_value(ui, &mut config.theta, 0.1, "Theta ");
+        }
+        changed |= config_value(ui, &mut config.v_rest, 0.1, "V_rest ");
+        if matches!(
+            config.dynamics,
+            Dynamics::Lif | Dynamics::AdEx(_) | Dynamics::Custom(_)
+        ) {
+            changed |= config_value(ui, &mut config.v_reset, 0.1, "V_reset ");
+        }
+        changed |= config_value(ui, &mut config.tau_m, 0.1, "Tau_m ");
+        changed |= config_value(ui, &mut config.tau_syn, 0.1, "Tau_syn ");
+
+        if let Dynamics::Izhikevich(params) = &mut config.dynamics {
+            ui.separator();
+            ui.label("Preset");
+            let current = params.matching_preset();
+            for preset in IzhikevichPreset::ALL {
+                if ui
+                    .selectable_label(current == Some(preset), preset.name())
+                    .clicked()
+                {
+                    *params = IzhikevichParams::preset(preset);
+                    changed = true;
+                }
+            }
+            changed |= config_value(ui, &mut params.a, 0.001, "a ");
+            changed |= config_value(ui, &mut params.b, 0.01, "b ");
+            changed |= config_value(ui, &mut params.c, 0.1, "c ");
+            changed |= config_value(ui, &mut params.d, 0.1, "d ");
+            changed |= config_value(ui, &mut params.v_peak, 0.1, "V_peak ");
+        }
+
+        if let Dynamics::AdEx(params) = &mut config.dynamics {
+            ui.separator();
+            ui.label("Adaptation");
+            changed |= positive_value(ui, &mut params.c_m, 1.0, "C_m pF ");
+            changed |= config_value(ui, &mut params.a, 0.1, "a nS ");
+            changed |= config_value(ui, &mut params.b, 1.0, "b pA ");
+            changed |= positive_value(ui, &mut params.tau_w, 1.0, "Tau_w ");
+            changed |= config_value(ui, &mut params.delta_t, 0.1, "Delta_T ");
+            changed |= config_value(ui, &mut params.v_t, 0.1, "V_T ");
+            changed |= config_value(ui, &mut params.v_peak, 0.1, "V_peak ");
+        }
+
+        if let Dynamics::HodgkinHuxley(params) = &mut config.dynamics {
+            ui.separator();
+            ui.label("Channels");
+            changed |= positive_value(ui, &mut params.c_m, 0.01, "C_m ");
+            changed |= config_value(ui, &mut params.g_na, 0.1, "g_Na ");
+            changed |= config_value(ui, &mut params.g_k, 0.1, "g_K ");
+            changed |= config_value(ui, &mut params.g_l, 0.01, "g_L ");
+            changed |= config_value(ui, &mut params.e_na, 0.1, "E_Na ");
+            changed |= config_value(ui, &mut params.e_k, 0.1, "E_K ");
+            changed |= config_value(ui, &mut params.e_l, 0.1, "E_L ");
+            changed |= config_value(ui, &mut params.threshold, 0.1, "Threshold ");
+            changed |= positive_value(ui, &mut params.max_step, 0.001, "Max step ");
+        }
     });
 
     changed
@@ -3,17 +3,19 @@ use std::collections::HashMap;
 use anyhow::Context;
 use egui_snarl::{InPinId, NodeId, OutPinId};
 
+#[derive(Debug)]
 pub struct CompiledGraph {
     pub network: Network,
     pub node_to_neuron: HashMap<NodeId, NeuronId>,
     pub inputs: Vec<(NodeId, NeuronId)>,
+    /// Probes and the neurons they watch. Nothing reads them yet.
+    #[allow(dead_code)]
     pub outputs: Vec<(NodeId, NeuronId)>,
 }
 
-use crate::{
-    gui::builder::{GraphNode, WireKey},
-    neuro::{motifs::ConnectionSpec, network::Network, neuron::NeuronId},
-};
+use neurorisc::neuro::{motifs::ConnectionSpec, network::Network, neuron::NeuronId};
+
+use crate::gui::builder::{GraphNode, WireKey};
 
 pub fn compile_snarl_to_network(
     snarl: &egui_snarl::Snarl<GraphNode>,
@@ -29,7 +31,14 @@ pub fn compile_snarl_to_network(
     for (node_id, node) in snarl.node_ids() {
         match node {
             GraphNode::Neuron(spec) => {
-                let nid = network.add_neuron(spec.kind, spec.config);
+                spec.config.dynamics.validate().with_context(|| {
+                    if spec.label.is_empty() {
+                        format!("neuron {}", node_id.0)
+                    } else {
+                        format!("neuron '{}'", spec.label)
+                    }
+                })?;
+                let nid = network.add_neuron(spec.kind, spec.config.clone());
                 node_to_neuron.insert(node_id, nid);
             }
             GraphNode::Stimulus(_) => {
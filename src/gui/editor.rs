@@ -4,15 +4,14 @@ use egui_snarl::{
     ui::{PinInfo, SnarlViewer, WireStyle},
 };
 
-use crate::{
-    gui::builder::{GraphNode, NeuronSpec, WireKey, neuron_body},
-    neuro::{
-        motifs::ConnectionSpec,
-        neuron::{NeuronConfig, NeuronKind},
-        stimuli::{StimulusMode, StimulusSpec},
-    },
+use neurorisc::neuro::{
+    motifs::ConnectionSpec,
+    neuron::{NeuronConfig, NeuronKind},
+    stimuli::{StimulusMode, StimulusSpec},
 };
 
+use crate::gui::builder::{GraphNode, NeuronSpec, WireKey, neuron_body};
+
 pub struct GraphViewer<'a> {
     pub wires: &'a mut std::collections::HashMap<WireKey, ConnectionSpec>,
     pub dirty: &'a mut bool,
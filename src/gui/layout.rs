@@ -3,12 +3,11 @@ use std::collections::{HashMap, VecDeque};
 use egui::{Pos2, Vec2};
 use egui_snarl::NodeId;
 
-use crate::{
-    gui::{
-        builder::{GraphNode, WireKey},
-        compiler::CompiledGraph,
-    },
-    neuro::motifs::ConnectionSpec,
+use neurorisc::neuro::motifs::ConnectionSpec;
+
+use crate::gui::{
+    builder::{GraphNode, WireKey},
+    compiler::CompiledGraph,
 };
 
 #[derive(Clone, Copy)]
@@ -55,20 +54,20 @@ fn node_color(
 ) -> egui::Color32 {
     match node {
         GraphNode::Neuron(_) => {
-            if let Some(compiled) = compiled {
-                if let Some(&nid) = compiled.node_to_neuron.get(&node_id) {
-                    let neuron = &compiled.network.neurons[nid];
-                    // Reuse voltage for brightness.
-                    let v = neuron.state.v;
-                    let t = ((v - -70.0) / (-45.0 - -70.0)).clamp(0.0, 1.0) as f32;
-                    let base = get_neuron_color(nid);
-                    return egui::Color32::from_rgba_premultiplied(
-                        (base.r() as f32 * (0.4 + 0.6 * t)) as u8,
-                        (base.g() as f32 * (0.4 + 0.6 * t)) as u8,
-                        (base.b() as f32 * (0.4 + 0.6 * t)) as u8,
-                        (255.0 * (0.6 + 0.4 * t)) as u8,
-                    );
-                }
+            if let Some(compiled) = compiled
+                && let Some(&nid) = compiled.node_to_neuron.get(&node_id)
+            {
+                let neuron = &compiled.network.neurons[nid];
+                // Reuse voltage for brightness.
+                let v = neuron.state.v;
+                let t = ((v - -70.0) / (-45.0 - -70.0)).clamp(0.0, 1.0) as f32;
+                let base = get_neuron_color(nid);
+                return egui::Color32::from_rgba_premultiplied(
+                    (base.r() as f32 * (0.4 + 0.6 * t)) as u8,
+                    (base.g() as f32 * (0.4 + 0.6 * t)) as u8,
+                    (base.b() as f32 * (0.4 + 0.6 * t)) as u8,
+                    (255.0 * (0.6 + 0.4 * t)) as u8,
+                );
             }
             get_neuron_color(index_hint)
         }
@@ -132,7 +131,7 @@ pub fn draw_snarl_topology(
         indegree.insert(*id, 0);
     }
 
-    for (key, _) in wires {
+    for key in wires.keys() {
         let from = key.from.node;
         let to = key.to.node;
         adjacency.entry(from).or_default().push(to);
@@ -145,10 +144,10 @@ pub fn draw_snarl_topology(
         .filter_map(|(&id, &deg)| if deg == 0 { Some(id) } else { None })
         .collect();
 
-    if queue.is_empty() {
-        if let Some(&(id, _)) = nodes.first() {
-            queue.push_back(id);
-        }
+    if queue.is_empty()
+        && let Some(&(id, _)) = nodes.first()
+    {
+        queue.push_back(id);
     }
 
     while let Some(id) = queue.pop_front() {
@@ -163,12 +162,12 @@ pub fn draw_snarl_topology(
                     *entry = next_layer;
                 }
 
-                if let Some(deg) = indegree.get_mut(&n) {
-                    if *deg > 0 {
-                        *deg -= 1;
-                        if *deg == 0 {
-                            queue.push_back(n);
-                        }
+                if let Some(deg) = indegree.get_mut(&n)
+                    && *deg > 0
+                {
+                    *deg -= 1;
+                    if *deg == 0 {
+                        queue.push_back(n);
                     }
                 }
             }
@@ -3,3 +3,4 @@ pub mod builder;
 pub mod compiler;
 pub mod editor;
 pub mod layout;
+pub mod snapshot;
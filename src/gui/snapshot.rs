@@ -0,0 +1,66 @@
+use std::{collections::HashMap, fs, path::Path};
+
+use anyhow::Context;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use neurorisc::neuro::motifs::ConnectionSpec;
+
+use crate::gui::{
+    builder::{GraphNode, WireKey},
+    compiler::{CompiledGraph, compile_snarl_to_network},
+};
+
+/// An editor graph as the GUI saves it.
+#[derive(Serialize, Deserialize)]
+pub struct GraphSnapshot {
+    pub snarl: egui_snarl::Snarl<GraphNode>,
+    #[serde(serialize_with = "wires_as_list", deserialize_with = "wires_from_list")]
+    pub wires: HashMap<WireKey, ConnectionSpec>,
+}
+
+#[derive(Serialize)]
+pub struct GraphSnapshotRef<'a> {
+    pub snarl: &'a egui_snarl::Snarl<GraphNode>,
+    #[serde(serialize_with = "wires_as_list")]
+    pub wires: &'a HashMap<WireKey, ConnectionSpec>,
+}
+
+/// JSON object keys must be strings, so wires are saved as `[key, spec]`
+/// pairs instead of a map.
+fn wires_as_list<S: Serializer>(
+    wires: &HashMap<WireKey, ConnectionSpec>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(wires)
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SavedWires {
+    List(Vec<(WireKey, ConnectionSpec)>),
+    /// Saves from before wires were a list; only ever written without wires.
+    Map(HashMap<String, ConnectionSpec>),
+}
+
+fn wires_from_list<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<HashMap<WireKey, ConnectionSpec>, D::Error> {
+    match SavedWires::deserialize(deserializer)? {
+        SavedWires::List(wires) => Ok(wires.into_iter().collect()),
+        SavedWires::Map(wires) if wires.is_empty() => Ok(HashMap::new()),
+        SavedWires::Map(_) => Err(serde::de::Error::custom("wires must be a list")),
+    }
+}
+
+impl GraphSnapshot {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let data = fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+
+        serde_json::from_str(&data).with_context(|| format!("failed to parse {}", path.display()))
+    }
+
+    pub fn compile(&self) -> anyhow::Result<CompiledGraph> {
+        compile_snarl_to_network(&self.snarl, &self.wires)
+    }
+}
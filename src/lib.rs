@@ -0,0 +1,28 @@
+//! NeuroRISC: a RISC-V core whose ALU and registers can run on spiking
+//! neural circuits.
+//!
+//! - [`neuro`] is the simulator: neurons, networks, motifs and stimuli.
+//! - [`core`] is the RV32IMC core, its assembler and disassembler, and the
+//!   spiking circuits it can execute on.
+//!
+//! The types most harnesses need are re-exported here. The `neurorisc`
+//! binary, with its subcommands and graph editor, is built on the same API.
+
+pub mod core;
+pub mod neuro;
+
+pub use crate::{
+    core::{
+        decoder::{decode, decode_instruction, encode, encode_instruction},
+        instructions::{DecodedInstruction, Instruction},
+    },
+    neuro::{
+        model::{ModelState, NeuronModel, register_model},
+        motifs::{self, ConnectionSpec, InputSpec, OutputSpec},
+        network::Network,
+        neuron::{
+            AdExParams, Dynamics, HodgkinHuxleyParams, IzhikevichParams, IzhikevichPreset, Neuron,
+            NeuronConfig, NeuronId, NeuronKind, NeuronState,
+        },
+    },
+};
@@ -1,18 +1,73 @@
 use crate::cli::{Cli, Commands};
 use clap::Parser;
+use neurorisc::core::spiking_regfile::RegisterFileParams;
 
 mod cli;
 mod commands;
-mod core;
 mod gui;
-mod neuro;
 
 fn main() -> anyhow::Result<()> {
     let cli = Cli::parse();
 
     match cli.command {
-        Commands::Run => commands::run::run()?,
+        Commands::Run {
+            graph,
+            steps,
+            dt,
+            seed,
+            sample_every,
+            out,
+            format,
+        } => commands::run::run(
+            graph.as_deref(),
+            steps,
+            dt,
+            seed,
+            sample_every,
+            &out,
+            &format,
+        )?,
         Commands::Gui => commands::gui::run()?,
+        Commands::Exec {
+            program,
+            spiking,
+            max_steps,
+            trace,
+            sensory,
+            spiking_regs,
+            reg_noise,
+        } => {
+            let spiking_regs = spiking_regs.then(|| RegisterFileParams {
+                noise: reg_noise,
+                ..RegisterFileParams::default()
+            });
+            commands::exec::run(
+                &program,
+                spiking,
+                max_steps,
+                trace.as_deref(),
+                sensory,
+                spiking_regs,
+            )?
+        }
+        Commands::Debug {
+            program,
+            gdb,
+            spiking,
+        } => commands::debug::run(&program, gdb, spiking)?,
+        Commands::Compliance {
+            dir,
+            spiking,
+            filter,
+            max_steps,
+        } => commands::compliance::run(&dir, spiking, filter.as_deref(), max_steps)?,
+        Commands::Retention {
+            hold,
+            max_noise,
+            levels,
+            population,
+            seed,
+        } => commands::retention::run(hold, max_noise, levels, population, seed)?,
     }
 
     Ok(())
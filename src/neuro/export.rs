@@ -0,0 +1,353 @@
+//! Writes the result of a simulation run to files for offline analysis.
+//!
+//! Every format carries the timestep and each neuron's label and kind next to
+//! the data. Samples and spikes are indexed by the tick they were computed in,
+//! so tick `n` is at `n * dt` ms.
+
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+use serde::Serialize;
+
+use crate::neuro::{
+    neuron::{NeuronId, NeuronKind},
+    recorder::Spike,
+};
+
+/// Membrane potentials and spikes of every neuron over a run.
+#[derive(Clone, Debug, Default)]
+pub struct Recording {
+    pub dt: f64,
+    pub labels: Vec<String>,
+    pub kinds: Vec<NeuronKind>,
+    /// Tick of every sample.
+    pub ticks: Vec<u64>,
+    /// One trace per neuron, one entry per sample.
+    pub voltages: Vec<Vec<f64>>,
+    /// In firing order.
+    pub spikes: Vec<Spike>,
+}
+
+/// Names of the arrays in the NPY and NPZ outputs.
+const ARRAY_NAMES: [&str; 7] = [
+    "dt",
+    "labels",
+    "kinds",
+    "ticks",
+    "voltages",
+    "spike_ticks",
+    "spike_neurons",
+];
+
+fn kind_name(kind: NeuronKind) -> &'static str {
+    match kind {
+        NeuronKind::Excitatory => "Excitatory",
+        NeuronKind::Inhibitory => "Inhibitory",
+    }
+}
+
+/// `stem` with `suffix` appended to its file name, e.g. `out` + `_spikes.csv`.
+fn sibling(stem: &Path, suffix: &str) -> PathBuf {
+    let name = stem
+        .file_stem()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    stem.with_file_name(format!("{name}{suffix}"))
+}
+
+fn create(path: &Path) -> anyhow::Result<BufWriter<File>> {
+    let file =
+        File::create(path).with_context(|| format!("failed to create {}", path.display()))?;
+    Ok(BufWriter::new(file))
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[derive(Serialize)]
+struct JsonNeuron<'a> {
+    id: NeuronId,
+    label: &'a str,
+    kind: NeuronKind,
+}
+
+#[derive(Serialize)]
+struct JsonRecording<'a> {
+    dt: f64,
+    neurons: Vec<JsonNeuron<'a>>,
+    ticks: &'a [u64],
+    voltages: &'a [Vec<f64>],
+    spikes: &'a [Spike],
+}
+
+impl Recording {
+    fn csv_header(&self, out: &mut impl Write) -> std::io::Result<()> {
+        writeln!(out, "# dt_ms: {}", self.dt)?;
+        let labels: Vec<String> = self.labels.iter().map(|label| csv_field(label)).collect();
+        writeln!(out, "# labels: {}", labels.join(","))?;
+        let kinds: Vec<&str> = self.kinds.iter().map(|&kind| kind_name(kind)).collect();
+        writeln!(out, "# kinds: {}", kinds.join(","))
+    }
+
+    /// The files `write_csv` writes for `stem`: voltages, then spikes.
+    pub fn csv_paths(stem: &Path) -> [PathBuf; 2] {
+        [sibling(stem, "_voltages.csv"), sibling(stem, "_spikes.csv")]
+    }
+
+    /// Writes `<stem>_voltages.csv`, one row per sample and one column per
+    /// neuron, and `<stem>_spikes.csv`, one row per spike. Metadata is in
+    /// `#` comment lines at the top of both.
+    pub fn write_csv(&self, stem: &Path) -> anyhow::Result<Vec<PathBuf>> {
+        let [voltages_path, spikes_path] = Self::csv_paths(stem);
+        let mut out = create(&voltages_path)?;
+        self.csv_header(&mut out)?;
+        write!(out, "tick,time_ms")?;
+        for label in &self.labels {
+            write!(out, ",{}", csv_field(label))?;
+        }
+        writeln!(out)?;
+        for (sample, &tick) in self.ticks.iter().enumerate() {
+            write!(out, "{tick},{}", tick as f64 * self.dt)?;
+            for trace in &self.voltages {
+                write!(out, ",{}", trace[sample])?;
+            }
+            writeln!(out)?;
+        }
+        out.flush()?;
+
+        let mut out = create(&spikes_path)?;
+        self.csv_header(&mut out)?;
+        writeln!(out, "tick,time_ms,neuron,label,kind")?;
+        for &Spike { tick, neuron } in &self.spikes {
+            writeln!(
+                out,
+                "{tick},{},{neuron},{},{}",
+                tick as f64 * self.dt,
+                csv_field(&self.labels[neuron]),
+                kind_name(self.kinds[neuron])
+            )?;
+        }
+        out.flush()?;
+
+        Ok(vec![voltages_path, spikes_path])
+    }
+
+    pub fn write_json(&self, path: &Path) -> anyhow::Result<()> {
+        let recording = JsonRecording {
+            dt: self.dt,
+            neurons: self
+                .labels
+                .iter()
+                .zip(&self.kinds)
+                .enumerate()
+                .map(|(id, (label, &kind))| JsonNeuron { id, label, kind })
+                .collect(),
+            ticks: &self.ticks,
+            voltages: &self.voltages,
+            spikes: &self.spikes,
+        };
+
+        let mut out = create(path)?;
+        serde_json::to_writer(&mut out, &recording)?;
+        out.flush()?;
+        Ok(())
+    }
+
+    /// The files `write_npy` writes for `stem`, one per array.
+    pub fn npy_paths(stem: &Path) -> Vec<PathBuf> {
+        ARRAY_NAMES
+            .iter()
+            .map(|name| sibling(stem, &format!("_{name}.npy")))
+            .collect()
+    }
+
+    /// The arrays stored by `write_npy` and `write_npz`, in `ARRAY_NAMES`
+    /// order.
+    fn arrays(&self) -> Vec<(&'static str, Vec<u8>)> {
+        let samples = self.ticks.len();
+        let voltages: Vec<f64> = self.voltages.iter().flatten().copied().collect();
+        let kinds: Vec<&str> = self.kinds.iter().map(|&kind| kind_name(kind)).collect();
+        let labels: Vec<&str> = self.labels.iter().map(String::as_str).collect();
+
+        let data = [
+            npy::f64s(&[self.dt], &[]),
+            npy::strings(&labels),
+            npy::strings(&kinds),
+            npy::u64s(&self.ticks, &[samples]),
+            npy::f64s(&voltages, &[self.voltages.len(), samples]),
+            npy::u64s(
+                &self
+                    .spikes
+                    .iter()
+                    .map(|spike| spike.tick)
+                    .collect::<Vec<_>>(),
+                &[self.spikes.len()],
+            ),
+            npy::u64s(
+                &self
+                    .spikes
+                    .iter()
+                    .map(|spike| spike.neuron as u64)
+                    .collect::<Vec<_>>(),
+                &[self.spikes.len()],
+            ),
+        ];
+        ARRAY_NAMES.into_iter().zip(data).collect()
+    }
+
+    /// Writes one `<stem>_<name>.npy` per array: `dt`, `labels`, `kinds`,
+    /// `ticks`, `voltages` (neurons x samples), `spike_ticks` and
+    /// `spike_neurons`.
+    pub fn write_npy(&self, stem: &Path) -> anyhow::Result<Vec<PathBuf>> {
+        let mut written = Vec::new();
+        for ((_, data), path) in self.arrays().into_iter().zip(Self::npy_paths(stem)) {
+            let mut out = create(&path)?;
+            out.write_all(&data)?;
+            out.flush()?;
+            written.push(path);
+        }
+        Ok(written)
+    }
+
+    /// Writes the arrays of `write_npy` into a single `np.load`-able archive.
+    pub fn write_npz(&self, path: &Path) -> anyhow::Result<()> {
+        let mut out = create(path)?;
+        npy::write_zip(&mut out, &self.arrays())?;
+        out.flush()?;
+        Ok(())
+    }
+}
+
+/// Just enough of the NumPy `.npy` format and of uncompressed zip archives
+/// to write `.npy` and `.npz` files.
+mod npy {
+    use std::io::Write;
+
+    fn shape(dims: &[usize]) -> String {
+        match dims {
+            [] => "()".to_string(),
+            [n] => format!("({n},)"),
+            _ => {
+                let dims: Vec<String> = dims.iter().map(usize::to_string).collect();
+                format!("({})", dims.join(", "))
+            }
+        }
+    }
+
+    fn array(descr: &str, dims: &[usize], data: &[u8]) -> Vec<u8> {
+        let mut header = format!(
+            "{{'descr': '{descr}', 'fortran_order': False, 'shape': {}, }}",
+            shape(dims)
+        );
+        // Magic, version and header length take 10 bytes, and the whole
+        // preamble is padded to a multiple of 64 ending in a newline.
+        let total = (10 + header.len() + 1).next_multiple_of(64);
+        header.extend(std::iter::repeat_n(' ', total - 10 - header.len() - 1));
+        header.push('\n');
+
+        let mut out = Vec::with_capacity(total + data.len());
+        out.extend_from_slice(b"\x93NUMPY\x01\x00");
+        out.extend_from_slice(&(header.len() as u16).to_le_bytes());
+        out.extend_from_slice(header.as_bytes());
+        out.extend_from_slice(data);
+        out
+    }
+
+    pub fn f64s(values: &[f64], dims: &[usize]) -> Vec<u8> {
+        let data: Vec<u8> = values.iter().flat_map(|v| v.to_le_bytes()).collect();
+        array("<f8", dims, &data)
+    }
+
+    pub fn u64s(values: &[u64], dims: &[usize]) -> Vec<u8> {
+        let data: Vec<u8> = values.iter().flat_map(|v| v.to_le_bytes()).collect();
+        array("<u8", dims, &data)
+    }
+
+    /// A 1-d array of fixed-width UTF-32 strings, NumPy's `str_` dtype.
+    pub fn strings(values: &[&str]) -> Vec<u8> {
+        let width = values
+            .iter()
+            .map(|s| s.chars().count())
+            .max()
+            .unwrap_or(0)
+            .max(1);
+
+        let mut data = Vec::with_capacity(values.len() * width * 4);
+        for s in values {
+            let chars = s.chars().count();
+            data.extend(s.chars().flat_map(|c| (c as u32).to_le_bytes()));
+            data.extend(std::iter::repeat_n(0, (width - chars) * 4));
+        }
+        array(&format!("<U{width}"), &[values.len()], &data)
+    }
+
+    /// Writes `arrays` as stored (uncompressed) `<name>.npy` zip entries.
+    pub fn write_zip(out: &mut impl Write, arrays: &[(&str, Vec<u8>)]) -> anyhow::Result<()> {
+        const VERSION: u16 = 20;
+        // 1980-01-01 00:00, the earliest DOS date.
+        const DATE: u16 = 0x21;
+
+        let mut offset = 0u32;
+        let mut central = Vec::new();
+
+        for (name, data) in arrays {
+            let name = format!("{name}.npy");
+            let crc = crc32fast::hash(data);
+            let size = u32::try_from(data.len())
+                .map_err(|_| anyhow::anyhow!("npz archives are limited to 4 GiB"))?;
+
+            let mut local = Vec::new();
+            local.extend_from_slice(&0x0403_4b50u32.to_le_bytes());
+            local.extend_from_slice(&VERSION.to_le_bytes());
+            local.extend_from_slice(&0u16.to_le_bytes()); // flags
+            local.extend_from_slice(&0u16.to_le_bytes()); // stored
+            local.extend_from_slice(&0u16.to_le_bytes()); // time
+            local.extend_from_slice(&DATE.to_le_bytes());
+            local.extend_from_slice(&crc.to_le_bytes());
+            local.extend_from_slice(&size.to_le_bytes());
+            local.extend_from_slice(&size.to_le_bytes());
+            local.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            local.extend_from_slice(&0u16.to_le_bytes()); // extra
+            local.extend_from_slice(name.as_bytes());
+
+            central.extend_from_slice(&0x0201_4b50u32.to_le_bytes());
+            central.extend_from_slice(&VERSION.to_le_bytes()); // made by
+            central.extend_from_slice(&local[4..30]);
+            central.extend_from_slice(&0u16.to_le_bytes()); // comment
+            central.extend_from_slice(&0u16.to_le_bytes()); // disk
+            central.extend_from_slice(&0u16.to_le_bytes()); // internal attributes
+            central.extend_from_slice(&0u32.to_le_bytes()); // external attributes
+            central.extend_from_slice(&offset.to_le_bytes());
+            central.extend_from_slice(name.as_bytes());
+
+            out.write_all(&local)?;
+            out.write_all(data)?;
+            offset = u32::try_from(offset as usize + local.len() + data.len())
+                .map_err(|_| anyhow::anyhow!("npz archives are limited to 4 GiB"))?;
+        }
+
+        let entries = arrays.len() as u16;
+        let mut end = Vec::new();
+        end.extend_from_slice(&0x0605_4b50u32.to_le_bytes());
+        end.extend_from_slice(&0u16.to_le_bytes()); // disk
+        end.extend_from_slice(&0u16.to_le_bytes()); // disk with the directory
+        end.extend_from_slice(&entries.to_le_bytes());
+        end.extend_from_slice(&entries.to_le_bytes());
+        end.extend_from_slice(&(central.len() as u32).to_le_bytes());
+        end.extend_from_slice(&offset.to_le_bytes());
+        end.extend_from_slice(&0u16.to_le_bytes()); // comment
+
+        out.write_all(&central)?;
+        out.write_all(&end)?;
+        Ok(())
+    }
+}
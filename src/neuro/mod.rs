@@ -1,4 +1,8 @@
+pub mod export;
+pub mod model;
+pub mod monitor;
 pub mod motifs;
 pub mod network;
 pub mod neuron;
+pub mod recorder;
 pub mod stimuli;
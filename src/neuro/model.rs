@@ -0,0 +1,884 @@
+//! Neuron dynamics behind one interface, so `Network::tick` does not need to
+//! know which model a neuron runs.
+//!
+//! A model owns the per-neuron variables it needs besides the shared
+//! `NeuronState`, as its `NeuronModel::State`. The built-in models are
+//! selected through `Dynamics`, which is also what saved graphs store. Models
+//! defined outside this crate implement `NeuronModel`, are made loadable with
+//! `register_model`, and are selected with `Dynamics::custom`; saved graphs
+//! store their name and parameters.
+
+use std::{
+    any::Any,
+    fmt,
+    sync::{Arc, PoisonError, RwLock},
+};
+
+use anyhow::Context;
+use serde::{Serialize, de::DeserializeOwned};
+
+use crate::neuro::neuron::{
+    AdExParams, Dynamics, HodgkinHuxleyParams, IzhikevichParams, NeuronConfig, NeuronState,
+};
+
+/// One tick of a neuron is `integrate`, then `spiked`, then `reset` if it
+/// spiked. While `refractory_left` is nonzero, `refractory` runs instead of
+/// all three. Synaptic conductances are decayed by `Network` beforehand and
+/// arrive as `input`, in mV/ms.
+///
+/// The model's parameters are `Self`, and are saved with the graph.
+pub trait NeuronModel:
+    fmt::Debug + PartialEq + Send + Sync + Serialize + DeserializeOwned + 'static
+{
+    /// Per-neuron variables the model keeps besides `NeuronState`.
+    type State: Clone + fmt::Debug + Send + Sync + 'static;
+
+    /// Name saved graphs refer to the model by.
+    const NAME: &'static str;
+
+    /// State of a new neuron, whose `v` starts at `config.v_rest`.
+    fn init(&self, config: &NeuronConfig) -> Self::State;
+
+    /// Advances the neuron by `dt` ms with `input` held constant.
+    fn integrate(
+        &self,
+        config: &NeuronConfig,
+        state: &mut NeuronState,
+        model_state: &mut Self::State,
+        input: f64,
+        dt: f64,
+    );
+
+    /// Whether the neuron fired during the last `integrate`.
+    fn spiked(&self, config: &NeuronConfig, state: &NeuronState, model_state: &Self::State)
+    -> bool;
+
+    /// Applies the after-spike reset. Setting `refractory_left` holds the
+    /// neuron in `refractory` for that many ticks.
+    fn reset(&self, config: &NeuronConfig, state: &mut NeuronState, model_state: &mut Self::State);
+
+    /// Runs instead of `integrate` on refractory ticks; the neuron cannot
+    /// fire during them.
+    fn refractory(
+        &self,
+        config: &NeuronConfig,
+        state: &mut NeuronState,
+        _model_state: &mut Self::State,
+        _dt: f64,
+    ) {
+        state.v = config.v_reset;
+    }
+
+    /// One of the model's variables by name, for monitors.
+    fn variable(&self, _model_state: &Self::State, _name: &str) -> Option<f64> {
+        None
+    }
+
+    /// Rejects parameters the model cannot integrate, such as a zero it
+    /// divides by. Graphs are checked when they are compiled.
+    fn validate(&self) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+/// Fails unless `value` is finite and greater than zero.
+fn ensure_positive(name: &str, value: f64) -> anyhow::Result<()> {
+    anyhow::ensure!(
+        value.is_finite() && value > 0.0,
+        "{name} must be positive, got {value}"
+    );
+    Ok(())
+}
+
+/// A neuron's `NeuronModel::State`, whichever model it runs.
+pub struct ModelState(Box<dyn AnyState>);
+
+trait AnyState: Any + fmt::Debug + Send + Sync {
+    fn clone_box(&self) -> Box<dyn AnyState>;
+}
+
+impl<T: Clone + fmt::Debug + Send + Sync + 'static> AnyState for T {
+    fn clone_box(&self) -> Box<dyn AnyState> {
+        Box::new(self.clone())
+    }
+}
+
+impl ModelState {
+    fn new<T: Clone + fmt::Debug + Send + Sync + 'static>(state: T) -> Self {
+        Self(Box::new(state))
+    }
+
+    /// `None` if the neuron runs a model with a different state type.
+    pub fn get<T: 'static>(&self) -> Option<&T> {
+        (self.0.as_ref() as &dyn Any).downcast_ref()
+    }
+
+    pub fn get_mut<T: 'static>(&mut self) -> Option<&mut T> {
+        (self.0.as_mut() as &mut dyn Any).downcast_mut()
+    }
+}
+
+impl Clone for ModelState {
+    fn clone(&self) -> Self {
+        Self(self.0.clone_box())
+    }
+}
+
+impl fmt::Debug for ModelState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// `NeuronModel` with its state type erased, so `Dynamics` can hand out any
+/// model.
+trait DynModel: Send + Sync {
+    fn name(&self) -> &'static str;
+    fn init(&self, config: &NeuronConfig) -> ModelState;
+    fn integrate(
+        &self,
+        config: &NeuronConfig,
+        state: &mut NeuronState,
+        model_state: &mut ModelState,
+        input: f64,
+        dt: f64,
+    );
+    fn spiked(&self, config: &NeuronConfig, state: &NeuronState, model_state: &ModelState) -> bool;
+    fn reset(&self, config: &NeuronConfig, state: &mut NeuronState, model_state: &mut ModelState);
+    fn refractory(
+        &self,
+        config: &NeuronConfig,
+        state: &mut NeuronState,
+        model_state: &mut ModelState,
+        dt: f64,
+    );
+    fn variable(&self, model_state: &ModelState, name: &str) -> Option<f64>;
+    fn validate(&self) -> anyhow::Result<()>;
+    fn params(&self) -> serde_json::Result<serde_json::Value>;
+    fn as_any(&self) -> &dyn Any;
+    fn eq_dyn(&self, other: &dyn DynModel) -> bool;
+    fn debug(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result;
+}
+
+/// The state of `model`, replaced by a fresh one if the neuron switched
+/// models since it was built.
+fn own_state<'a, M: NeuronModel>(
+    model: &M,
+    config: &NeuronConfig,
+    model_state: &'a mut ModelState,
+) -> &'a mut M::State {
+    if model_state.get::<M::State>().is_none() {
+        *model_state = ModelState::new(model.init(config));
+    }
+    model_state
+        .get_mut()
+        .expect("model state was just initialized")
+}
+
+impl<M: NeuronModel> DynModel for M {
+    fn name(&self) -> &'static str {
+        M::NAME
+    }
+
+    fn init(&self, config: &NeuronConfig) -> ModelState {
+        ModelState::new(NeuronModel::init(self, config))
+    }
+
+    fn integrate(
+        &self,
+        config: &NeuronConfig,
+        state: &mut NeuronState,
+        model_state: &mut ModelState,
+        input: f64,
+        dt: f64,
+    ) {
+        let model_state = own_state(self, config, model_state);
+        NeuronModel::integrate(self, config, state, model_state, input, dt);
+    }
+
+    /// `integrate` always runs first and replaces a foreign state, so a
+    /// mismatch here is a bug in the caller.
+    fn spiked(&self, config: &NeuronConfig, state: &NeuronState, model_state: &ModelState) -> bool {
+        let model_state = model_state.get();
+        debug_assert!(
+            model_state.is_some(),
+            "{} neuron holds another model's state",
+            M::NAME
+        );
+        model_state.is_some_and(|model_state| NeuronModel::spiked(self, config, state, model_state))
+    }
+
+    fn reset(&self, config: &NeuronConfig, state: &mut NeuronState, model_state: &mut ModelState) {
+        let model_state = own_state(self, config, model_state);
+        NeuronModel::reset(self, config, state, model_state);
+    }
+
+    fn refractory(
+        &self,
+        config: &NeuronConfig,
+        state: &mut NeuronState,
+        model_state: &mut ModelState,
+        dt: f64,
+    ) {
+        let model_state = own_state(self, config, model_state);
+        NeuronModel::refractory(self, config, state, model_state, dt);
+    }
+
+    fn variable(&self, model_state: &ModelState, name: &str) -> Option<f64> {
+        let model_state = model_state.get();
+        debug_assert!(
+            model_state.is_some(),
+            "{} neuron holds another model's state",
+            M::NAME
+        );
+        NeuronModel::variable(self, model_state?, name)
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        NeuronModel::validate(self)
+    }
+
+    fn params(&self) -> serde_json::Result<serde_json::Value> {
+        serde_json::to_value(self)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn eq_dyn(&self, other: &dyn DynModel) -> bool {
+        other.as_any().downcast_ref::<M>() == Some(self)
+    }
+
+    fn debug(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+impl Dynamics {
+    /// A model defined outside this crate. Register it with `register_model`
+    /// too, or graphs saved with it won't load.
+    pub fn custom<M: NeuronModel>(model: M) -> Self {
+        Dynamics::Custom(CustomModel(Arc::new(model)))
+    }
+
+    fn model(&self) -> &dyn DynModel {
+        match self {
+            Dynamics::Lif => &Lif,
+            Dynamics::Izhikevich(params) => params,
+            Dynamics::AdEx(params) => params,
+            Dynamics::HodgkinHuxley(params) => params,
+            Dynamics::Custom(custom) => custom.0.as_ref(),
+        }
+    }
+
+    /// `NeuronModel::NAME` of the model.
+    pub fn name(&self) -> &'static str {
+        self.model().name()
+    }
+
+    pub fn init(&self, config: &NeuronConfig) -> ModelState {
+        self.model().init(config)
+    }
+
+    pub fn integrate(
+        &self,
+        config: &NeuronConfig,
+        state: &mut NeuronState,
+        model_state: &mut ModelState,
+        input: f64,
+        dt: f64,
+    ) {
+        self.model()
+            .integrate(config, state, model_state, input, dt);
+    }
+
+    pub fn spiked(
+        &self,
+        config: &NeuronConfig,
+        state: &NeuronState,
+        model_state: &ModelState,
+    ) -> bool {
+        self.model().spiked(config, state, model_state)
+    }
+
+    pub fn reset(
+        &self,
+        config: &NeuronConfig,
+        state: &mut NeuronState,
+        model_state: &mut ModelState,
+    ) {
+        self.model().reset(config, state, model_state);
+    }
+
+    pub fn refractory(
+        &self,
+        config: &NeuronConfig,
+        state: &mut NeuronState,
+        model_state: &mut ModelState,
+        dt: f64,
+    ) {
+        self.model().refractory(config, state, model_state, dt);
+    }
+
+    pub fn variable(&self, model_state: &ModelState, name: &str) -> Option<f64> {
+        self.model().variable(model_state, name)
+    }
+
+    pub fn validate(&self) -> anyhow::Result<()> {
+        self.model()
+            .validate()
+            .with_context(|| format!("invalid {} parameters", self.name()))
+    }
+}
+
+/// Leaky integrate-and-fire, configured entirely by `NeuronConfig`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Lif;
+
+impl NeuronModel for Lif {
+    type State = ();
+
+    const NAME: &'static str = "Lif";
+
+    fn init(&self, _config: &NeuronConfig) {}
+
+    fn integrate(
+        &self,
+        config: &NeuronConfig,
+        state: &mut NeuronState,
+        _model_state: &mut (),
+        input: f64,
+        dt: f64,
+    ) {
+        let i_leak = -(state.v - config.v_rest) / config.tau_m;
+
+        state.v += (i_leak + input) * dt;
+    }
+
+    fn spiked(&self, config: &NeuronConfig, state: &NeuronState, _model_state: &()) -> bool {
+        state.v >= config.theta
+    }
+
+    fn reset(&self, config: &NeuronConfig, state: &mut NeuronState, _model_state: &mut ()) {
+        state.v = config.v_reset;
+        state.refractory_left = config.refractory_period; // ticks
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct IzhikevichState {
+    /// Recovery variable.
+    pub u: f64,
+}
+
+impl NeuronModel for IzhikevichParams {
+    type State = IzhikevichState;
+
+    const NAME: &'static str = "Izhikevich";
+
+    fn init(&self, config: &NeuronConfig) -> IzhikevichState {
+        IzhikevichState {
+            u: self.b * config.v_rest,
+        }
+    }
+
+    fn integrate(
+        &self,
+        _config: &NeuronConfig,
+        state: &mut NeuronState,
+        model_state: &mut IzhikevichState,
+        input: f64,
+        dt: f64,
+    ) {
+        let v = state.v;
+        let u = model_state.u;
+
+        state.v += (0.04 * v * v + 5.0 * v + 140.0 - u + input) * dt;
+        model_state.u += self.a * (self.b * v - u) * dt;
+    }
+
+    fn spiked(
+        &self,
+        _config: &NeuronConfig,
+        state: &NeuronState,
+        _model_state: &IzhikevichState,
+    ) -> bool {
+        state.v >= self.v_peak
+    }
+
+    fn reset(
+        &self,
+        _config: &NeuronConfig,
+        state: &mut NeuronState,
+        model_state: &mut IzhikevichState,
+    ) {
+        state.v = self.c;
+        model_state.u += self.d;
+    }
+
+    fn variable(&self, model_state: &IzhikevichState, name: &str) -> Option<f64> {
+        (name == "u").then_some(model_state.u)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct AdExState {
+    /// Adaptation current (pA).
+    pub w: f64,
+}
+
+impl AdExParams {
+    fn adapt(&self, config: &NeuronConfig, v: f64, model_state: &mut AdExState, dt: f64) {
+        let w = model_state.w;
+        model_state.w += (self.a * (v - config.v_rest) - w) * (dt / self.tau_w);
+    }
+}
+
+impl NeuronModel for AdExParams {
+    type State = AdExState;
+
+    const NAME: &'static str = "AdEx";
+
+    fn init(&self, _config: &NeuronConfig) -> AdExState {
+        AdExState::default()
+    }
+
+    fn integrate(
+        &self,
+        config: &NeuronConfig,
+        state: &mut NeuronState,
+        model_state: &mut AdExState,
+        input: f64,
+        dt: f64,
+    ) {
+        let v = state.v;
+        let w = model_state.w;
+
+        self.adapt(config, v, model_state, dt);
+
+        let i_spike = if self.delta_t > 0.0 {
+            self.delta_t * ((v - self.v_t) / self.delta_t).exp()
+        } else {
+            0.0
+        };
+        let i_leak = (-(v - config.v_rest) + i_spike) / config.tau_m;
+
+        state.v += (i_leak + input - w / self.c_m) * dt;
+    }
+
+    fn spiked(
+        &self,
+        _config: &NeuronConfig,
+        state: &NeuronState,
+        _model_state: &AdExState,
+    ) -> bool {
+        state.v >= self.v_peak
+    }
+
+    fn reset(&self, config: &NeuronConfig, state: &mut NeuronState, model_state: &mut AdExState) {
+        state.v = config.v_reset;
+        model_state.w += self.b;
+        state.refractory_left = config.refractory_period;
+    }
+
+    /// `w` keeps adapting while `v` is held.
+    fn refractory(
+        &self,
+        config: &NeuronConfig,
+        state: &mut NeuronState,
+        model_state: &mut AdExState,
+        dt: f64,
+    ) {
+        self.adapt(config, state.v, model_state, dt);
+        state.v = config.v_reset;
+    }
+
+    fn variable(&self, model_state: &AdExState, name: &str) -> Option<f64> {
+        (name == "w").then_some(model_state.w)
+    }
+
+    /// A `delta_t` of zero is allowed and drops the exponential term.
+    fn validate(&self) -> anyhow::Result<()> {
+        ensure_positive("C_m", self.c_m)?;
+        ensure_positive("tau_w", self.tau_w)?;
+        anyhow::ensure!(
+            self.delta_t.is_finite() && self.delta_t >= 0.0,
+            "Delta_T must not be negative, got {}",
+            self.delta_t
+        );
+        Ok(())
+    }
+}
+
+/// `x / (1 - exp(-x / k))`, continued through its removable singularity at
+/// `x = 0`.
+fn exp_ratio(x: f64, k: f64) -> f64 {
+    if (x / k).abs() < 1e-6 {
+        k * (1.0 + x / (2.0 * k))
+    } else {
+        x / (1.0 - (-x / k).exp())
+    }
+}
+
+/// Opening and closing rates `(alpha, beta)` of the m, h and n gates at `v`,
+/// in 1/ms.
+fn gate_rates(v: f64) -> [(f64, f64); 3] {
+    [
+        (
+            0.1 * exp_ratio(v + 40.0, 10.0),
+            4.0 * (-(v + 65.0) / 18.0).exp(),
+        ),
+        (
+            0.07 * (-(v + 65.0) / 20.0).exp(),
+            1.0 / (1.0 + (-(v + 35.0) / 10.0).exp()),
+        ),
+        (
+            0.01 * exp_ratio(v + 55.0, 10.0),
+            0.125 * (-(v + 65.0) / 80.0).exp(),
+        ),
+    ]
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct HodgkinHuxleyState {
+    /// Na activation.
+    pub m: f64,
+    /// Na inactivation.
+    pub h: f64,
+    /// K activation.
+    pub n: f64,
+    /// Whether `v` crossed `threshold` during the last tick, which may have
+    /// happened between sub-steps.
+    pub crossed: bool,
+}
+
+/// Most sub-steps one Hodgkin-Huxley tick is split into, so a tiny
+/// `max_step` cannot stall the simulation.
+const HH_MAX_SUBSTEPS: f64 = 10_000.0;
+
+impl HodgkinHuxleyParams {
+    /// Steady-state `(m, h, n)` at a fixed `v`.
+    pub fn steady_gates(v: f64) -> (f64, f64, f64) {
+        let [m, h, n] = gate_rates(v).map(|(alpha, beta)| alpha / (alpha + beta));
+        (m, h, n)
+    }
+}
+
+impl NeuronModel for HodgkinHuxleyParams {
+    type State = HodgkinHuxleyState;
+
+    const NAME: &'static str = "HodgkinHuxley";
+
+    fn init(&self, config: &NeuronConfig) -> HodgkinHuxleyState {
+        let (m, h, n) = Self::steady_gates(config.v_rest);
+        HodgkinHuxleyState {
+            m,
+            h,
+            n,
+            crossed: false,
+        }
+    }
+
+    /// Gates are integrated exponentially (Rush-Larsen) and `v` with forward
+    /// Euler, in equal sub-steps no longer than `max_step`, but at most
+    /// `HH_MAX_SUBSTEPS` of them. Conductances are held for the tick, so
+    /// `input` is the same for every sub-step.
+    fn integrate(
+        &self,
+        _config: &NeuronConfig,
+        state: &mut NeuronState,
+        model_state: &mut HodgkinHuxleyState,
+        input: f64,
+        dt: f64,
+    ) {
+        let steps = (dt / self.max_step).ceil().clamp(1.0, HH_MAX_SUBSTEPS);
+        let step = dt / steps;
+        let HodgkinHuxleyState { m, h, n, crossed } = model_state;
+        *crossed = false;
+
+        for _ in 0..steps as u32 {
+            let v = state.v;
+
+            let i_na = self.g_na * m.powi(3) * *h * (v - self.e_na);
+            let i_k = self.g_k * n.powi(4) * (v - self.e_k);
+            let i_l = self.g_l * (v - self.e_l);
+
+            for (gate, (alpha, beta)) in [&mut *m, &mut *h, &mut *n].into_iter().zip(gate_rates(v))
+            {
+                let rate = alpha + beta;
+                let x_inf = alpha / rate;
+                *gate = x_inf + (*gate - x_inf) * (-step * rate).exp();
+            }
+
+            state.v += (input - (i_na + i_k + i_l) / self.c_m) * step;
+
+            if v < self.threshold && state.v >= self.threshold {
+                *crossed = true;
+            }
+        }
+    }
+
+    fn spiked(
+        &self,
+        _config: &NeuronConfig,
+        _state: &NeuronState,
+        model_state: &HodgkinHuxleyState,
+    ) -> bool {
+        model_state.crossed
+    }
+
+    /// The channels repolarize the membrane on their own.
+    fn reset(
+        &self,
+        _config: &NeuronConfig,
+        _state: &mut NeuronState,
+        _model_state: &mut HodgkinHuxleyState,
+    ) {
+    }
+
+    fn variable(&self, model_state: &HodgkinHuxleyState, name: &str) -> Option<f64> {
+        match name {
+            "m" => Some(model_state.m),
+            "h" => Some(model_state.h),
+            "n" => Some(model_state.n),
+            _ => None,
+        }
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        ensure_positive("C_m", self.c_m)?;
+        ensure_positive("max_step", self.max_step)
+    }
+}
+
+/// A model defined outside this crate, with its parameters. Saved graphs
+/// store its name and parameters, and load it through `register_model`.
+#[derive(Clone)]
+pub struct CustomModel(Arc<dyn DynModel>);
+
+impl CustomModel {
+    pub fn name(&self) -> &'static str {
+        self.0.name()
+    }
+
+    /// The model's parameters, if it is an `M`.
+    pub fn params<M: NeuronModel>(&self) -> Option<&M> {
+        self.0.as_any().downcast_ref()
+    }
+}
+
+impl fmt::Debug for CustomModel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.debug(f)
+    }
+}
+
+impl PartialEq for CustomModel {
+    /// Same model with equal parameters.
+    fn eq(&self, other: &Self) -> bool {
+        self.0.eq_dyn(other.0.as_ref())
+    }
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct SavedCustomModel {
+    name: String,
+    #[serde(default)]
+    params: serde_json::Value,
+}
+
+impl serde::Serialize for CustomModel {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        SavedCustomModel {
+            name: self.name().to_string(),
+            params: self.0.params().map_err(serde::ser::Error::custom)?,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for CustomModel {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let saved = SavedCustomModel::deserialize(deserializer)?;
+        let load = registered_loader(&saved.name).ok_or_else(|| {
+            serde::de::Error::custom(format!(
+                "neuron model '{}' is not registered; call `register_model` first",
+                saved.name
+            ))
+        })?;
+
+        load(saved.params).map_err(|e| {
+            serde::de::Error::custom(format!(
+                "invalid parameters for neuron model '{}': {e}",
+                saved.name
+            ))
+        })
+    }
+}
+
+type Loader = fn(serde_json::Value) -> serde_json::Result<CustomModel>;
+
+static CUSTOM_MODELS: RwLock<Vec<(&'static str, Loader)>> = RwLock::new(Vec::new());
+
+/// Makes graphs saved with `M` loadable, replacing any model registered
+/// under the same name.
+pub fn register_model<M: NeuronModel>() {
+    let mut models = CUSTOM_MODELS
+        .write()
+        .unwrap_or_else(PoisonError::into_inner);
+    models.retain(|&(name, _)| name != M::NAME);
+    models.push((M::NAME, |params| {
+        serde_json::from_value::<M>(params).map(|model| CustomModel(Arc::new(model)))
+    }));
+}
+
+fn registered_loader(name: &str) -> Option<Loader> {
+    let models = CUSTOM_MODELS.read().unwrap_or_else(PoisonError::into_inner);
+    models
+        .iter()
+        .find(|&&(registered, _)| registered == name)
+        .map(|&(_, load)| load)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::neuro::{
+        monitor::{StateMonitor, Variable},
+        network::Network,
+        neuron::{Neuron, NeuronKind},
+    };
+
+    #[test]
+    fn rejects_parameters_that_are_divided_by() {
+        for max_step in [0.0, -0.01, f64::NAN] {
+            let params = HodgkinHuxleyParams {
+                max_step,
+                ..Default::default()
+            };
+            assert!(Dynamics::HodgkinHuxley(params).validate().is_err());
+        }
+        let params = HodgkinHuxleyParams {
+            c_m: 0.0,
+            ..Default::default()
+        };
+        assert!(Dynamics::HodgkinHuxley(params).validate().is_err());
+
+        for params in [
+            AdExParams {
+                c_m: 0.0,
+                ..Default::default()
+            },
+            AdExParams {
+                tau_w: -1.0,
+                ..Default::default()
+            },
+            AdExParams {
+                delta_t: f64::INFINITY,
+                ..Default::default()
+            },
+        ] {
+            assert!(Dynamics::AdEx(params).validate().is_err());
+        }
+
+        assert!(
+            Dynamics::HodgkinHuxley(Default::default())
+                .validate()
+                .is_ok()
+        );
+        assert!(Dynamics::AdEx(Default::default()).validate().is_ok());
+        let params = AdExParams {
+            delta_t: 0.0,
+            ..Default::default()
+        };
+        assert!(Dynamics::AdEx(params).validate().is_ok());
+    }
+
+    #[test]
+    fn caps_hodgkin_huxley_sub_steps() {
+        let config = NeuronConfig {
+            dynamics: Dynamics::HodgkinHuxley(HodgkinHuxleyParams {
+                max_step: 1e-300,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let mut neuron = Neuron::new(NeuronKind::Excitatory, config.clone());
+        config.dynamics.integrate(
+            &config,
+            &mut neuron.state,
+            &mut neuron.model_state,
+            0.0,
+            0.1,
+        );
+        assert!(neuron.state.v.is_finite());
+    }
+
+    /// Integrates `v` towards `target`, counting ticks in its own state.
+    #[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+    struct Counter {
+        target: f64,
+    }
+
+    impl NeuronModel for Counter {
+        type State = u32;
+
+        const NAME: &'static str = "Counter";
+
+        fn init(&self, _config: &NeuronConfig) -> u32 {
+            0
+        }
+
+        fn integrate(
+            &self,
+            _config: &NeuronConfig,
+            state: &mut NeuronState,
+            ticks: &mut u32,
+            _input: f64,
+            _dt: f64,
+        ) {
+            state.v = self.target;
+            *ticks += 1;
+        }
+
+        fn spiked(&self, _config: &NeuronConfig, _state: &NeuronState, _ticks: &u32) -> bool {
+            false
+        }
+
+        fn reset(&self, _config: &NeuronConfig, _state: &mut NeuronState, _ticks: &mut u32) {}
+
+        fn variable(&self, ticks: &u32, name: &str) -> Option<f64> {
+            (name == "ticks").then_some(*ticks as f64)
+        }
+    }
+
+    #[test]
+    fn custom_models_compare_save_and_record_by_value() {
+        register_model::<Counter>();
+        let dynamics = Dynamics::custom(Counter { target: 1.0 });
+        assert_eq!(dynamics, Dynamics::custom(Counter { target: 1.0 }));
+        assert_ne!(dynamics, Dynamics::custom(Counter { target: 2.0 }));
+        assert_ne!(dynamics, Dynamics::Lif);
+
+        let saved = serde_json::to_string(&dynamics).unwrap();
+        assert_eq!(serde_json::from_str::<Dynamics>(&saved).unwrap(), dynamics);
+
+        let mut network = Network::new();
+        let config = NeuronConfig {
+            dynamics,
+            ..Default::default()
+        };
+        let id = network.add_neuron(NeuronKind::Excitatory, config);
+        network.resize_events();
+        let ticks = Variable::Model("ticks");
+        network.monitor = Some(StateMonitor::new(vec![id], vec![ticks]));
+        for _ in 0..3 {
+            network.tick(0.1);
+        }
+
+        let monitor = network.monitor.as_ref().unwrap();
+        let trace: Vec<f64> = monitor.trace(id, ticks).unwrap().iter().copied().collect();
+        assert_eq!(trace, [1.0, 2.0, 3.0]);
+    }
+}
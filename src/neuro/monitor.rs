@@ -0,0 +1,172 @@
+use std::collections::VecDeque;
+
+use crate::neuro::neuron::{Neuron, NeuronId};
+
+/// A neuron state variable a `StateMonitor` can record.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Variable {
+    V,
+    GExc,
+    GInh,
+    RefractoryLeft,
+    U,
+    W,
+    M,
+    H,
+    N,
+    /// Any variable of the neuron's model, by the name its
+    /// `NeuronModel::variable` answers to. This is how variables of models
+    /// defined outside this crate are recorded.
+    Model(&'static str),
+}
+
+impl Variable {
+    /// Every variable with its own variant.
+    pub const ALL: [Variable; 9] = [
+        Variable::V,
+        Variable::GExc,
+        Variable::GInh,
+        Variable::RefractoryLeft,
+        Variable::U,
+        Variable::W,
+        Variable::M,
+        Variable::H,
+        Variable::N,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Variable::V => "v",
+            Variable::GExc => "g_exc",
+            Variable::GInh => "g_inh",
+            Variable::RefractoryLeft => "refractory_left",
+            Variable::U => "u",
+            Variable::W => "w",
+            Variable::M => "m",
+            Variable::H => "h",
+            Variable::N => "n",
+            Variable::Model(name) => name,
+        }
+    }
+
+    /// NaN for a model variable the neuron's model doesn't have.
+    pub fn read(self, neuron: &Neuron) -> f64 {
+        let state = &neuron.state;
+        match self {
+            Variable::V => state.v,
+            Variable::GExc => state.g_exc,
+            Variable::GInh => state.g_inh,
+            Variable::RefractoryLeft => state.refractory_left as f64,
+            Variable::U
+            | Variable::W
+            | Variable::M
+            | Variable::H
+            | Variable::N
+            | Variable::Model(_) => neuron
+                .config
+                .dynamics
+                .variable(&neuron.model_state, self.name())
+                .unwrap_or(f64::NAN),
+        }
+    }
+}
+
+/// Samples chosen variables of chosen neurons at the end of every
+/// `interval`-th tick, starting with tick 0.
+///
+/// Unbounded by default. With a capacity it keeps only the most recent
+/// samples, like a ring buffer.
+#[derive(Clone, Debug)]
+pub struct StateMonitor {
+    neurons: Vec<NeuronId>,
+    variables: Vec<Variable>,
+    interval: u64,
+    capacity: Option<usize>,
+    ticks: VecDeque<u64>,
+    /// One trace per neuron and variable, variables varying fastest.
+    traces: Vec<VecDeque<f64>>,
+}
+
+impl StateMonitor {
+    pub fn new(neurons: Vec<NeuronId>, variables: Vec<Variable>) -> Self {
+        let traces = vec![VecDeque::new(); neurons.len() * variables.len()];
+        Self {
+            neurons,
+            variables,
+            interval: 1,
+            capacity: None,
+            ticks: VecDeque::new(),
+            traces,
+        }
+    }
+
+    /// Samples every `interval` ticks; 0 is treated as 1.
+    pub fn set_interval(&mut self, interval: u64) {
+        self.interval = interval.max(1);
+    }
+
+    pub fn set_capacity(&mut self, capacity: Option<usize>) {
+        self.capacity = capacity;
+        self.trim();
+    }
+
+    fn trim(&mut self) {
+        if let Some(capacity) = self.capacity {
+            while self.ticks.len() > capacity {
+                self.ticks.pop_front();
+                for trace in &mut self.traces {
+                    trace.pop_front();
+                }
+            }
+        }
+    }
+
+    pub(crate) fn record(&mut self, tick: u64, neurons: &[Neuron]) {
+        if !tick.is_multiple_of(self.interval) {
+            return;
+        }
+
+        self.ticks.push_back(tick);
+        let mut traces = self.traces.iter_mut();
+        for &id in &self.neurons {
+            let neuron = neurons.get(id);
+            for &variable in &self.variables {
+                let value = neuron.map_or(f64::NAN, |neuron| variable.read(neuron));
+                if let Some(trace) = traces.next() {
+                    trace.push_back(value);
+                }
+            }
+        }
+        self.trim();
+    }
+
+    /// Forgets every sample, keeping the configuration.
+    pub fn clear(&mut self) {
+        self.ticks.clear();
+        self.traces.iter_mut().for_each(VecDeque::clear);
+    }
+
+    pub fn neurons(&self) -> &[NeuronId] {
+        &self.neurons
+    }
+
+    pub fn variables(&self) -> &[Variable] {
+        &self.variables
+    }
+
+    pub fn interval(&self) -> u64 {
+        self.interval
+    }
+
+    /// Tick of every sample.
+    pub fn ticks(&self) -> &VecDeque<u64> {
+        &self.ticks
+    }
+
+    /// `None` if `neuron` or `variable` is not monitored.
+    pub fn trace(&self, neuron: NeuronId, variable: Variable) -> Option<&VecDeque<f64>> {
+        let n = self.neurons.iter().position(|&id| id == neuron)?;
+        let v = self.variables.iter().position(|&var| var == variable)?;
+        self.traces.get(n * self.variables.len() + v)
+    }
+}
@@ -29,7 +29,7 @@ pub struct InputSpec {
     pub connection: ConnectionSpec,
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Debug)]
 pub struct OutputSpec {
     pub config: NeuronConfig,
     pub connection: ConnectionSpec,
@@ -235,6 +235,7 @@ pub fn feedback_inhibition(
     Ok((forward, inhibitor))
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn cross_inhibition_following(
     network: &mut Network,
     a_pre: NeuronId,
@@ -1,10 +1,25 @@
-use crate::neuro::neuron::{Neuron, NeuronConfig, NeuronId, NeuronKind};
+use crate::neuro::{
+    monitor::StateMonitor,
+    neuron::{Neuron, NeuronConfig, NeuronId, NeuronKind},
+    recorder::SpikeRecorder,
+};
 
+#[derive(Clone, Debug)]
 pub struct Network {
     pub neurons: Vec<Neuron>,
     pub adjacency_list: Vec<Vec<(NeuronId, f64, u32)>>, // (id, weight, delay)
     pub events: Vec<Vec<(NeuronId, f64)>>,
     pub t: usize,
+    /// Neurons that fired during the last tick.
+    pub spiked: Vec<NeuronId>,
+    pub recorder: Option<SpikeRecorder>,
+    pub monitor: Option<StateMonitor>,
+}
+
+impl Default for Network {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Network {
@@ -14,6 +29,25 @@ impl Network {
             adjacency_list: vec![],
             events: vec![],
             t: 0,
+            spiked: vec![],
+            recorder: None,
+            monitor: None,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        for neuron in &mut self.neurons {
+            *neuron = Neuron::new(neuron.kind, neuron.config.clone());
+        }
+
+        self.events.iter_mut().for_each(Vec::clear);
+        self.t = 0;
+        self.spiked.clear();
+        if let Some(recorder) = &mut self.recorder {
+            recorder.clear();
+        }
+        if let Some(monitor) = &mut self.monitor {
+            monitor.clear();
         }
     }
 
@@ -54,11 +88,18 @@ impl Network {
             }
         }
 
-        let mut spiked: Vec<NeuronId> = Vec::new();
+        let mut spiked = std::mem::take(&mut self.spiked);
+        spiked.clear();
 
         for (id, neuron) in self.neurons.iter_mut().enumerate() {
-            let state = &mut neuron.state;
-            let config = neuron.config;
+            let Neuron {
+                state,
+                config,
+                model_state,
+                ..
+            } = neuron;
+            let config = &*config;
+            let dynamics = &config.dynamics;
 
             let decay = (-dt / config.tau_syn).exp();
             state.g_exc *= decay;
@@ -66,20 +107,18 @@ impl Network {
 
             if state.refractory_left > 0 {
                 state.refractory_left -= 1;
-                state.v = config.v_reset;
+                dynamics.refractory(config, state, model_state, dt);
                 continue;
             }
 
-            let i_leak = -(state.v - config.v_rest);
-
             let i_exc = state.g_exc * (config.e_exc - state.v);
             let i_inh = state.g_inh * (config.e_inh - state.v);
+            let input = (i_exc + i_inh) / config.tau_m;
 
-            state.v += (i_leak + i_exc + i_inh) * (dt / config.tau_m);
+            dynamics.integrate(config, state, model_state, input, dt);
 
-            if state.v >= config.theta {
-                state.v = config.v_reset;
-                state.refractory_left = config.refractory_period; // ticks
+            if dynamics.spiked(config, state, model_state) {
+                dynamics.reset(config, state, model_state);
                 spiked.push(id);
             }
         }
@@ -96,6 +135,14 @@ impl Network {
             self.schedule_spike(target, weight, delay);
         }
 
+        if let Some(recorder) = &mut self.recorder {
+            recorder.record(self.t as u64, &spiked);
+        }
+        self.spiked = spiked;
+        if let Some(monitor) = &mut self.monitor {
+            monitor.record(self.t as u64, &self.neurons);
+        }
+
         self.t += 1;
     }
 
@@ -1,3 +1,5 @@
+use crate::neuro::model::{CustomModel, ModelState};
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub enum NeuronKind {
     Excitatory,
@@ -6,7 +8,9 @@ pub enum NeuronKind {
 
 pub type NeuronId = usize;
 
-#[derive(Clone, Copy, Debug)]
+/// State every model shares. Model-specific variables live in the
+/// neuron's `ModelState`.
+#[derive(Clone, Copy, Debug, serde::Serialize, serde::Deserialize)]
 pub struct NeuronState {
     pub v: f64,
     pub refractory_left: u32,
@@ -14,7 +18,7 @@ pub struct NeuronState {
     pub g_inh: f64,
 }
 
-#[derive(Clone, Copy, Debug, serde::Serialize, serde::Deserialize)]
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
 pub struct NeuronConfig {
     pub v_rest: f64,
     pub v_reset: f64,
@@ -24,6 +28,180 @@ pub struct NeuronConfig {
     pub tau_syn: f64,
     pub e_exc: f64,
     pub e_inh: f64,
+    #[serde(default)]
+    pub dynamics: Dynamics,
+}
+
+/// How a neuron's membrane evolves between spikes and when it fires. Each
+/// variant is a `NeuronModel`, run through the methods in `model`.
+///
+/// Every model receives the same conductance-based synaptic input,
+/// `(g_exc * (e_exc - v) + g_inh * (e_inh - v)) / tau_m` in mV/ms, so a
+/// connection weight means the same whichever model its target uses.
+#[derive(Clone, Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "model")]
+pub enum Dynamics {
+    /// Leaky integrate-and-fire: fires at `theta`, then is held at `v_reset`
+    /// for `refractory_period` ticks.
+    #[default]
+    Lif,
+    /// Izhikevich (2003). `theta`, `v_reset` and `refractory_period` are
+    /// unused.
+    Izhikevich(IzhikevichParams),
+    /// Adaptive exponential integrate-and-fire (Brette & Gerstner 2005), with
+    /// `v_rest` as the leak reversal `E_L` and `g_L = c_m / tau_m`. Fires at
+    /// `v_peak`, then is held at `v_reset` for `refractory_period` ticks while
+    /// `w` keeps adapting. `theta` is unused.
+    AdEx(AdExParams),
+    /// Hodgkin & Huxley (1952) with Na, K and leak channels. Fires when `v`
+    /// crosses `threshold` upwards; the channels themselves reset and
+    /// refract. `v_rest` is only the initial potential, and `theta`,
+    /// `v_reset` and `refractory_period` are unused.
+    HodgkinHuxley(HodgkinHuxleyParams),
+    /// A model defined outside this crate; see `Dynamics::custom`.
+    Custom(CustomModel),
+}
+
+/// `v' = 0.04v^2 + 5v + 140 - u + I`, `u' = a(bv - u)`, and when `v` reaches
+/// `v_peak`, `v = c` and `u += d`.
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct IzhikevichParams {
+    pub a: f64,
+    pub b: f64,
+    pub c: f64,
+    pub d: f64,
+    pub v_peak: f64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IzhikevichPreset {
+    RegularSpiking,
+    FastSpiking,
+    Chattering,
+    Bursting,
+}
+
+impl IzhikevichPreset {
+    pub const ALL: [IzhikevichPreset; 4] = [
+        IzhikevichPreset::RegularSpiking,
+        IzhikevichPreset::FastSpiking,
+        IzhikevichPreset::Chattering,
+        IzhikevichPreset::Bursting,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            IzhikevichPreset::RegularSpiking => "Regular spiking",
+            IzhikevichPreset::FastSpiking => "Fast spiking",
+            IzhikevichPreset::Chattering => "Chattering",
+            IzhikevichPreset::Bursting => "Bursting",
+        }
+    }
+}
+
+impl IzhikevichParams {
+    pub fn preset(preset: IzhikevichPreset) -> Self {
+        let (a, b, c, d) = match preset {
+            IzhikevichPreset::RegularSpiking => (0.02, 0.2, -65.0, 8.0),
+            IzhikevichPreset::FastSpiking => (0.1, 0.2, -65.0, 2.0),
+            IzhikevichPreset::Chattering => (0.02, 0.2, -50.0, 2.0),
+            // Intrinsically bursting.
+            IzhikevichPreset::Bursting => (0.02, 0.2, -55.0, 4.0),
+        };
+
+        Self {
+            a,
+            b,
+            c,
+            d,
+            v_peak: 30.0,
+        }
+    }
+
+    /// The preset these parameters match exactly, if any.
+    pub fn matching_preset(&self) -> Option<IzhikevichPreset> {
+        IzhikevichPreset::ALL
+            .into_iter()
+            .find(|&preset| Self::preset(preset) == *self)
+    }
+}
+
+/// `C v' = -g_L (v - E_L) + g_L delta_t exp((v - v_t) / delta_t) - w + I`,
+/// `tau_w w' = a (v - E_L) - w`, and `w += b` on every spike.
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct AdExParams {
+    /// Membrane capacitance (pF).
+    pub c_m: f64,
+    /// Subthreshold adaptation (nS).
+    pub a: f64,
+    /// Spike-triggered adaptation (pA).
+    pub b: f64,
+    /// Adaptation time constant (ms).
+    pub tau_w: f64,
+    /// Slope factor (mV); 0 makes the threshold hard.
+    pub delta_t: f64,
+    /// Threshold where the exponential takes over (mV).
+    pub v_t: f64,
+    pub v_peak: f64,
+}
+
+impl Default for AdExParams {
+    /// Adaptation and spike initiation of the regular-spiking cell of Brette &
+    /// Gerstner (2005). The leak still comes from `NeuronConfig`, whose
+    /// `tau_m` and `v_rest` defaults differ from that cell's 9.37 ms and
+    /// -70.6 mV.
+    fn default() -> Self {
+        Self {
+            c_m: 281.0,
+            a: 4.0,
+            b: 80.5,
+            tau_w: 144.0,
+            delta_t: 2.0,
+            v_t: -50.4,
+            v_peak: 20.0,
+        }
+    }
+}
+
+impl Default for IzhikevichParams {
+    fn default() -> Self {
+        Self::preset(IzhikevichPreset::RegularSpiking)
+    }
+}
+
+/// Conductances in mS/cm^2, potentials in mV and `c_m` in uF/cm^2, so
+/// channel currents come out in uA/cm^2.
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct HodgkinHuxleyParams {
+    pub c_m: f64,
+    pub g_na: f64,
+    pub g_k: f64,
+    pub g_l: f64,
+    pub e_na: f64,
+    pub e_k: f64,
+    pub e_l: f64,
+    /// Upward crossings of this potential are spikes (mV).
+    pub threshold: f64,
+    /// Longest integration step (ms); each tick is split into equal steps
+    /// no longer than this. Must be positive.
+    pub max_step: f64,
+}
+
+impl Default for HodgkinHuxleyParams {
+    /// The squid giant axon at 6.3 C, shifted to rest at -65 mV.
+    fn default() -> Self {
+        Self {
+            c_m: 1.0,
+            g_na: 120.0,
+            g_k: 36.0,
+            g_l: 0.3,
+            e_na: 50.0,
+            e_k: -77.0,
+            e_l: -54.387,
+            threshold: 0.0,
+            max_step: 0.01,
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -31,6 +209,7 @@ pub struct Neuron {
     pub kind: NeuronKind,
     pub state: NeuronState,
     pub config: NeuronConfig,
+    pub model_state: ModelState,
 }
 
 impl Neuron {
@@ -43,6 +222,7 @@ impl Neuron {
                 g_inh: 0.0,
                 refractory_left: 0,
             },
+            model_state: config.dynamics.init(&config),
             config,
         }
     }
@@ -59,6 +239,7 @@ impl Default for NeuronConfig {
             tau_syn: 5.0,         // Synaptic current decay (ms)
             e_exc: 0.0,           // Excitatory synapse reversal potential (mV)
             e_inh: -70.0,         // Inhibitory synapse reversal potential (mV)
+            dynamics: Dynamics::Lif,
         }
     }
 }
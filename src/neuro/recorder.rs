@@ -0,0 +1,140 @@
+use std::{collections::VecDeque, ops::Range};
+
+use crate::neuro::neuron::NeuronId;
+
+/// A neuron firing during tick `tick`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct Spike {
+    pub tick: u64,
+    pub neuron: NeuronId,
+}
+
+/// Keeps the spikes `Network::tick` reports, in firing order.
+///
+/// By default every neuron is recorded and nothing is ever dropped. With a
+/// selection only the selected neurons are recorded, and with a capacity the
+/// oldest spikes are dropped to make room for new ones.
+#[derive(Clone, Debug, Default)]
+pub struct SpikeRecorder {
+    /// Indexed by neuron; `None` records every neuron.
+    selection: Option<Vec<bool>>,
+    capacity: Option<usize>,
+    spikes: VecDeque<Spike>,
+    dropped: u64,
+}
+
+impl SpikeRecorder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keeps at most the `capacity` most recent spikes.
+    pub fn bounded(capacity: usize) -> Self {
+        Self {
+            capacity: Some(capacity),
+            ..Self::default()
+        }
+    }
+
+    /// Records only `neurons` from now on.
+    pub fn select(&mut self, neurons: &[NeuronId]) {
+        let len = neurons.iter().max().map_or(0, |&id| id + 1);
+        let mut selection = vec![false; len];
+        for &id in neurons {
+            selection[id] = true;
+        }
+        self.selection = Some(selection);
+    }
+
+    pub fn select_all(&mut self) {
+        self.selection = None;
+    }
+
+    pub fn is_selected(&self, neuron: NeuronId) -> bool {
+        match &self.selection {
+            Some(selection) => selection.get(neuron).copied().unwrap_or(false),
+            None => true,
+        }
+    }
+
+    pub fn set_capacity(&mut self, capacity: Option<usize>) {
+        self.capacity = capacity;
+        self.trim();
+    }
+
+    fn trim(&mut self) {
+        if let Some(capacity) = self.capacity {
+            while self.spikes.len() > capacity {
+                self.spikes.pop_front();
+                self.dropped += 1;
+            }
+        }
+    }
+
+    pub(crate) fn record(&mut self, tick: u64, spiked: &[NeuronId]) {
+        for &neuron in spiked {
+            if self.is_selected(neuron) {
+                self.spikes.push_back(Spike { tick, neuron });
+            }
+        }
+        self.trim();
+    }
+
+    /// Forgets every spike, keeping the selection and capacity.
+    pub fn clear(&mut self) {
+        self.spikes.clear();
+        self.dropped = 0;
+    }
+
+    pub fn len(&self) -> usize {
+        self.spikes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.spikes.is_empty()
+    }
+
+    /// Spikes dropped to stay within the capacity.
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+
+    pub fn spikes(&self) -> impl Iterator<Item = Spike> + '_ {
+        self.spikes.iter().copied()
+    }
+
+    /// Spikes fired during `ticks`.
+    pub fn window(&self, ticks: Range<u64>) -> impl Iterator<Item = Spike> + '_ {
+        let start = self
+            .spikes
+            .partition_point(|spike| spike.tick < ticks.start);
+        let end = self.spikes.partition_point(|spike| spike.tick < ticks.end);
+        self.spikes.range(start..end.max(start)).copied()
+    }
+
+    /// Spikes fired by `neuron` during `ticks`.
+    pub fn count(&self, neuron: NeuronId, ticks: Range<u64>) -> usize {
+        self.window(ticks)
+            .filter(|spike| spike.neuron == neuron)
+            .count()
+    }
+
+    /// Spikes fired by each of the first `neurons` neurons during `ticks`.
+    pub fn counts(&self, neurons: usize, ticks: Range<u64>) -> Vec<usize> {
+        let mut counts = vec![0; neurons];
+        for spike in self.window(ticks) {
+            if let Some(count) = counts.get_mut(spike.neuron) {
+                *count += 1;
+            }
+        }
+        counts
+    }
+
+    pub fn last_spike(&self, neuron: NeuronId) -> Option<u64> {
+        self.spikes
+            .iter()
+            .rev()
+            .find(|spike| spike.neuron == neuron)
+            .map(|spike| spike.tick)
+    }
+}
@@ -5,7 +5,7 @@ use serde::{Deserialize, Serialize};
 use crate::neuro::network::Network;
 use crate::neuro::neuron::NeuronId;
 
-#[derive(Clone, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct StimulusSpec {
     pub mode: StimulusMode,
     pub enabled: bool,
@@ -37,6 +37,7 @@ pub enum StimulusMode {
     },
 }
 
+#[derive(Debug)]
 pub struct StimulusRunner {
     dt: f64,
     stimuli: Vec<ActiveStimulus>,
@@ -91,10 +92,10 @@ impl StimulusRunner {
 
                 let start_time_ms = base_time_ms + *start as f64;
                 let stop_time_ms = stop.map(|s| base_time_ms + s as f64);
-                if let Some(stop_ms) = stop_time_ms {
-                    if stop_ms < start_time_ms {
-                        return;
-                    }
+                if let Some(stop_ms) = stop_time_ms
+                    && stop_ms < start_time_ms
+                {
+                    return;
                 }
 
                 let mut rng = StdRng::seed_from_u64(*seed);
@@ -105,7 +106,7 @@ impl StimulusRunner {
                     neuron_id,
                     mode: ActiveStimulusMode::Poisson {
                         rate: *rate,
-                        rng,
+                        rng: Box::new(rng),
                         next_time_ms,
                         stop_time_ms,
                         amp: 1.0,
@@ -147,9 +148,7 @@ impl StimulusRunner {
                 let interval_ticks = if *rate <= 0.0 {
                     1
                 } else {
-                    ((1000.0 / rate.max(1e-6)) / self.dt)
-                        .max(1.0)
-                        .round() as u64
+                    ((1000.0 / rate.max(1e-6)) / self.dt).max(1.0).round() as u64
                 };
 
                 self.stimuli.push(ActiveStimulus {
@@ -201,10 +200,10 @@ impl StimulusRunner {
                         } else {
                             let mut events = 0;
                             loop {
-                                if let Some(stop_ms) = *stop_time_ms {
-                                    if *next_time_ms > stop_ms {
-                                        break;
-                                    }
+                                if let Some(stop_ms) = *stop_time_ms
+                                    && *next_time_ms > stop_ms
+                                {
+                                    break;
                                 }
                                 if current_time_ms < *next_time_ms {
                                     break;
@@ -320,12 +319,14 @@ impl StimulusRunner {
     }
 }
 
+#[derive(Debug)]
 struct ActiveStimulus {
     stimulus_id: u64,
     neuron_id: NeuronId,
     mode: ActiveStimulusMode,
 }
 
+#[derive(Debug)]
 enum ActiveStimulusMode {
     ManualPulse {
         tick: u64,
@@ -333,7 +334,7 @@ enum ActiveStimulusMode {
     },
     Poisson {
         rate: f64,
-        rng: StdRng,
+        rng: Box<StdRng>,
         next_time_ms: f64,
         stop_time_ms: Option<f64>,
         amp: f64,